@@ -0,0 +1,324 @@
+//! Command-line parsing for the `yuri` driver.
+
+use std::fmt;
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+usage: yuri <command> [options] <files>...
+
+commands:
+    check    report errors without producing output
+    build    compile to an executable
+    run      build and run
+    fmt      format source files
+    test     build and run tests
+    lex      print the tokens of a file
+    parse    print the syntax tree of a file
+
+options:
+    --emit <stage>[,<stage>...]  print intermediate stages: tokens, ast, hir,
+                                 mir, state-machines, c, asm, obj
+    -O<level>                    optimization level: 0, 1, 2, 3, s (-O is -O2)
+    --target <triple>            target triple, defaults to the host
+    --format text|json           output format of --emit tokens
+    --color auto|always|never    colored diagnostics
+    --check                      with fmt, report unformatted files instead of
+                                 rewriting them
+    --width <columns>            with fmt, the line width to fit in (default 100)
+    -h, --help                   print this message
+    -V, --version                print the version
+";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Command {
+    Check,
+    Build,
+    Run,
+    Fmt,
+    Test,
+    Lex,
+    Parse,
+}
+
+impl Command {
+    fn from_name(s: &str) -> Option<Command> {
+        match s {
+            "check" => Some(Command::Check),
+            "build" => Some(Command::Build),
+            "run" => Some(Command::Run),
+            "fmt" => Some(Command::Fmt),
+            "test" => Some(Command::Test),
+            "lex" => Some(Command::Lex),
+            "parse" => Some(Command::Parse),
+            _ => None,
+        }
+    }
+}
+
+/// A compilation stage whose result can be printed with `--emit`, in pipeline
+/// order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Emit {
+    Tokens,
+    Ast,
+    Hir,
+    Mir,
+    StateMachines,
+    C,
+    Asm,
+    Obj,
+}
+
+impl Emit {
+    fn from_name(s: &str) -> Option<Emit> {
+        match s {
+            "tokens" => Some(Emit::Tokens),
+            "ast" => Some(Emit::Ast),
+            "hir" => Some(Emit::Hir),
+            "mir" => Some(Emit::Mir),
+            "state-machines" => Some(Emit::StateMachines),
+            "c" => Some(Emit::C),
+            "asm" => Some(Emit::Asm),
+            "obj" => Some(Emit::Obj),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Emit::Tokens => "tokens",
+            Emit::Ast => "ast",
+            Emit::Hir => "hir",
+            Emit::Mir => "mir",
+            Emit::StateMachines => "state-machines",
+            Emit::C => "c",
+            Emit::Asm => "asm",
+            Emit::Obj => "obj",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OptLevel {
+    O0,
+    O1,
+    O2,
+    O3,
+    Size,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Options {
+    pub command: Command,
+    pub inputs: Vec<PathBuf>,
+    /// Stages to print, sorted and without duplicates.
+    pub emit: Vec<Emit>,
+    pub opt_level: OptLevel,
+    pub target: Option<String>,
+    pub format: Format,
+    pub color: ColorChoice,
+    /// `yuri fmt --check`: only report the files that are not formatted.
+    pub check: bool,
+    /// The line width for `yuri fmt`, if not the default.
+    pub width: Option<usize>,
+}
+
+/// What the command line asks for.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Invocation {
+    Compile(Options),
+    Help,
+    Version,
+}
+
+/// A malformed command line.
+#[derive(Debug, PartialEq, Eq)]
+pub struct UsageError(pub String);
+
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+fn usage<T>(message: impl Into<String>) -> Result<T, UsageError> {
+    Err(UsageError(message.into()))
+}
+
+/// The value of an option given either as `--name value` or `--name=value`.
+fn value<'a>(
+    name: &str,
+    inline: Option<&'a str>,
+    args: &mut impl Iterator<Item = &'a String>,
+) -> Result<&'a str, UsageError> {
+    match inline {
+        Some(value) => Ok(value),
+        None => match args.next() {
+            Some(value) => Ok(value),
+            None => usage(format!("`{}` requires a value", name)),
+        },
+    }
+}
+
+pub fn parse(args: &[String]) -> Result<Invocation, UsageError> {
+    let mut args = args.iter();
+    let command = match args.next().map(String::as_str) {
+        None | Some("-h") | Some("--help") | Some("help") => return Ok(Invocation::Help),
+        Some("-V") | Some("--version") => return Ok(Invocation::Version),
+        Some(name) => match Command::from_name(name) {
+            Some(command) => command,
+            None => return usage(format!("unknown command `{}`", name)),
+        },
+    };
+    let mut options = Options {
+        command,
+        inputs: Vec::new(),
+        emit: Vec::new(),
+        opt_level: OptLevel::O0,
+        target: None,
+        format: Format::Text,
+        color: ColorChoice::Auto,
+        check: false,
+        width: None,
+    };
+
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => (&arg[..i], Some(&arg[i + 1..])),
+            _ => (arg.as_str(), None),
+        };
+        match name {
+            "-h" | "--help" => return Ok(Invocation::Help),
+            "-V" | "--version" => return Ok(Invocation::Version),
+            "--emit" => {
+                for stage in value(name, inline, &mut args)?.split(',') {
+                    match Emit::from_name(stage) {
+                        Some(emit) => options.emit.push(emit),
+                        None => return usage(format!("unknown stage `{}` for --emit", stage)),
+                    }
+                }
+            }
+            "--target" => options.target = Some(value(name, inline, &mut args)?.to_string()),
+            "--format" => {
+                options.format = match value(name, inline, &mut args)? {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    other => return usage(format!("unknown format `{}`", other)),
+                }
+            }
+            "--color" => {
+                options.color = match value(name, inline, &mut args)? {
+                    "auto" => ColorChoice::Auto,
+                    "always" => ColorChoice::Always,
+                    "never" => ColorChoice::Never,
+                    other => return usage(format!("unknown color choice `{}`", other)),
+                }
+            }
+            "--check" => options.check = true,
+            "--width" => {
+                let width = value(name, inline, &mut args)?;
+                match width.parse() {
+                    Ok(width) if width > 0 => options.width = Some(width),
+                    _ => return usage(format!("invalid width `{}`", width)),
+                }
+            }
+            _ if name.starts_with("-O") => {
+                options.opt_level = match &name[2..] {
+                    "" | "2" => OptLevel::O2,
+                    "0" => OptLevel::O0,
+                    "1" => OptLevel::O1,
+                    "3" => OptLevel::O3,
+                    "s" => OptLevel::Size,
+                    other => return usage(format!("unknown optimization level `{}`", other)),
+                }
+            }
+            _ if name.starts_with('-') && name != "-" => {
+                return usage(format!("unknown option `{}`", arg));
+            }
+            _ => options.inputs.push(PathBuf::from(arg)),
+        }
+    }
+
+    if options.inputs.is_empty() {
+        return usage("no input files");
+    }
+    options.emit.sort();
+    options.emit.dedup();
+    Ok(Invocation::Compile(options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_str(s: &str) -> Result<Invocation, UsageError> {
+        let args: Vec<String> = s.split_whitespace().map(String::from).collect();
+        parse(&args)
+    }
+
+    fn options(s: &str) -> Options {
+        match parse_str(s) {
+            Ok(Invocation::Compile(options)) => options,
+            other => panic!("{:?}", other),
+        }
+    }
+
+    #[test]
+    fn commands_and_options() {
+        let o = options(
+            "build -O --emit=mir,c --emit tokens --target x86_64-unknown-linux-gnu a.yuri b.yuri",
+        );
+        assert_eq!(o.command, Command::Build);
+        assert_eq!(o.emit, vec![Emit::Tokens, Emit::Mir, Emit::C]);
+        assert_eq!(o.opt_level, OptLevel::O2);
+        assert_eq!(o.target.as_deref(), Some("x86_64-unknown-linux-gnu"));
+        assert_eq!(
+            o.inputs,
+            vec![PathBuf::from("a.yuri"), PathBuf::from("b.yuri")]
+        );
+
+        let o = options("lex --format json --color never -Os a.yuri");
+        assert_eq!(o.command, Command::Lex);
+        assert_eq!(o.format, Format::Json);
+        assert_eq!(o.color, ColorChoice::Never);
+        assert_eq!(o.opt_level, OptLevel::Size);
+
+        let o = options("fmt --check --width=80 a.yuri");
+        assert_eq!(o.command, Command::Fmt);
+        assert!(o.check);
+        assert_eq!(o.width, Some(80));
+
+        assert_eq!(parse_str(""), Ok(Invocation::Help));
+        assert_eq!(parse_str("check --help"), Ok(Invocation::Help));
+        assert_eq!(parse_str("--version"), Ok(Invocation::Version));
+    }
+
+    #[test]
+    fn usage_errors() {
+        for args in &[
+            "frobnicate a.yuri",
+            "check",
+            "check --emit llvm a.yuri",
+            "check -O9 a.yuri",
+            "check --target",
+            "check --wat a.yuri",
+            "fmt --width 0 a.yuri",
+            "fmt --width wide a.yuri",
+        ] {
+            assert!(parse_str(args).is_err(), "{}", args);
+        }
+    }
+}
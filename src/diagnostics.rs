@@ -0,0 +1,220 @@
+//! Rendering errors and warnings for the terminal.
+
+use std::io::{self, Write};
+
+use unicode_width::UnicodeWidthStr;
+use yuri_lexer::{SourceMap, Span};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Level {
+    fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Note => "note",
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Level::Error => RED,
+            Level::Warning => YELLOW,
+            Level::Note => GREEN,
+        }
+    }
+}
+
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const GREEN: &str = "\x1b[32m";
+const BLUE: &str = "\x1b[34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub message: String,
+    pub span: Option<Span>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(level: Level, message: impl Into<String>) -> Self {
+        Diagnostic {
+            level,
+            message: message.into(),
+            span: None,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Diagnostic::new(Level::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Self {
+        Diagnostic::new(Level::Warning, message)
+    }
+
+    pub fn with_span(self, span: Span) -> Self {
+        Diagnostic {
+            span: Some(span),
+            ..self
+        }
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+}
+
+/// Writes diagnostics and counts the errors and warnings among them.
+pub struct Emitter<W> {
+    out: W,
+    color: bool,
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+impl<W: Write> Emitter<W> {
+    pub fn new(out: W, color: bool) -> Self {
+        Emitter {
+            out,
+            color,
+            errors: 0,
+            warnings: 0,
+        }
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    pub fn emit(&mut self, map: &SourceMap, diagnostic: &Diagnostic) -> io::Result<()> {
+        match diagnostic.level {
+            Level::Error => self.errors += 1,
+            Level::Warning => self.warnings += 1,
+            Level::Note => {}
+        }
+        let level = diagnostic.level;
+        let header = self.paint(&format!("{}{}", BOLD, level.color()), level.as_str());
+        let message = self.paint(BOLD, &diagnostic.message);
+        writeln!(self.out, "{}: {}", header, message)?;
+
+        if let Some(span) = diagnostic.span {
+            let file = map.file(span.file);
+            let pos = file.line_col_char(span.lo);
+            let line = pos.line as usize - 1;
+            let line_range = file.line_range(line);
+            let text = &file.src()[line_range.clone()];
+            let gutter = " ".repeat(pos.line.to_string().len());
+            let bar = self.paint(BLUE, "|");
+            writeln!(
+                self.out,
+                "{}{} {}:{}:{}",
+                gutter,
+                self.paint(BLUE, "-->"),
+                file.name(),
+                pos.line,
+                pos.col
+            )?;
+            writeln!(self.out, "{} {}", gutter, bar)?;
+            writeln!(
+                self.out,
+                "{} {} {}",
+                self.paint(BLUE, &pos.line.to_string()),
+                bar,
+                text
+            )?;
+            // Underline the part of the span on its first line, at least one
+            // column wide so that empty spans are visible. Columns are
+            // counted as the terminal shows them, two for a CJK character.
+            let lo = span.lo as usize;
+            let hi = (span.hi as usize).min(line_range.end).max(lo);
+            let indent = file.src()[line_range.start..lo].width();
+            let width = file.src()[lo..hi].width().max(1);
+            let carets = self.paint(&format!("{}{}", BOLD, level.color()), &"^".repeat(width));
+            writeln!(
+                self.out,
+                "{} {} {}{}",
+                gutter,
+                bar,
+                " ".repeat(indent),
+                carets
+            )?;
+        }
+        for note in &diagnostic.notes {
+            let header = self.paint(BOLD, Level::Note.as_str());
+            writeln!(self.out, "{}: {}", header, note)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render() {
+        let mut map = SourceMap::new();
+        let file = map.add_file(
+            "a.yuri",
+            "fn main() {\n    let ユ = $$;\n    let バッファ = 1 + ;\n}\n",
+        );
+        let mut emitter = Emitter::new(Vec::new(), false);
+        let diagnostic = Diagnostic::error("unexpected character")
+            .with_span(Span::new(file, 26, 28))
+            .with_note("remove it");
+        emitter.emit(&map, &diagnostic).unwrap();
+        let semi = map.file(file).src().rfind(';').unwrap() as u32;
+        let diagnostic = Diagnostic::error("expected expression, found `;`").with_span(Span::new(
+            file,
+            semi,
+            semi + 1,
+        ));
+        emitter.emit(&map, &diagnostic).unwrap();
+        emitter.emit(&map, &Diagnostic::warning("no span")).unwrap();
+        assert_eq!(
+            String::from_utf8(emitter.out).unwrap(),
+            "\
+error: unexpected character
+ --> a.yuri:2:13
+  |
+2 |     let ユ = $$;
+  |              ^^
+note: remove it
+error: expected expression, found `;`
+ --> a.yuri:3:20
+  |
+3 |     let バッファ = 1 + ;
+  |                        ^
+warning: no span
+"
+        );
+        assert_eq!((emitter.errors, emitter.warnings), (2, 1));
+    }
+
+    #[test]
+    fn color() {
+        let map = SourceMap::new();
+        let mut emitter = Emitter::new(Vec::new(), true);
+        emitter.emit(&map, &Diagnostic::error("oops")).unwrap();
+        assert_eq!(
+            String::from_utf8(emitter.out).unwrap(),
+            "\x1b[1m\x1b[31merror\x1b[0m: \x1b[1moops\x1b[0m\n"
+        );
+    }
+}
@@ -0,0 +1,245 @@
+//! Runs the compiler pipeline for a parsed command line.
+
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+use yuri_lexer::{lint_idents, FileId, Interner, LexError, Lexer, SourceMap, Token};
+
+use crate::cli::{ColorChoice, Command, Emit, Options};
+use crate::diagnostics::{Diagnostic, Emitter};
+use crate::lex;
+
+/// Whether the compilation succeeded. Diagnostics have been reported either
+/// way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Success,
+    Errors,
+}
+
+fn use_color(choice: ColorChoice) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => std::env::var_os("NO_COLOR").is_none() && io::stderr().is_terminal(),
+    }
+}
+
+struct Session<'o> {
+    options: &'o Options,
+    map: SourceMap,
+    interner: RefCell<Interner>,
+    emitter: Emitter<io::Stderr>,
+}
+
+impl<'o> Session<'o> {
+    fn emit(&mut self, diagnostic: Diagnostic) {
+        // There is nowhere left to report a failure to write to stderr.
+        let _ = self.emitter.emit(&self.map, &diagnostic);
+    }
+
+    /// Adds the input files to the source map, keeping the path each was
+    /// read from since the file's name is only for display.
+    fn read_inputs(&mut self) -> Vec<(FileId, &'o Path)> {
+        let options = self.options;
+        let mut files = Vec::new();
+        for path in &options.inputs {
+            match fs::read_to_string(path) {
+                Ok(src) => {
+                    files.push((self.map.add_file(path.display().to_string(), src), &**path))
+                }
+                Err(e) => self.emit(Diagnostic::error(format!(
+                    "cannot read {}: {}",
+                    path.display(),
+                    e
+                ))),
+            }
+        }
+        files
+    }
+
+    fn lex(&mut self, id: FileId) {
+        let file = self.map.file(id);
+        let mut tokens: Vec<Token> = Vec::new();
+        let mut errors: Vec<LexError> = Vec::new();
+        for item in Lexer::for_file(file) {
+            match item {
+                Ok(token) => tokens.push(token),
+                Err(e) => errors.push(e),
+            }
+        }
+        let mut diagnostics: Vec<_> = errors
+            .iter()
+            .map(|e| Diagnostic::error(e.to_string()).with_span(e.span))
+            .collect();
+        diagnostics.extend(lint_idents(&tokens).into_iter().map(|lint| {
+            let diagnostic = Diagnostic::warning(lint.kind.to_string()).with_span(lint.span);
+            match lint.other {
+                Some(other) => {
+                    let pos = file.line_col_char(other.lo);
+                    diagnostic.with_note(format!(
+                        "`{}` is used at {}:{}",
+                        file.text(other),
+                        pos.line,
+                        pos.col
+                    ))
+                }
+                None => diagnostic,
+            }
+        }));
+
+        if self.options.command == Command::Lex || self.options.emit.contains(&Emit::Tokens) {
+            let out = lex::dump(file, &tokens, &errors, self.options.format);
+            let _ = io::stdout().write_all(out.as_bytes());
+        }
+        for diagnostic in diagnostics {
+            self.emit(diagnostic);
+        }
+    }
+
+    fn parse(&mut self, id: FileId) {
+        let file = self.map.file(id);
+        let parse = yuri_parser::parse(Lexer::for_file(file), &self.interner);
+        if self.options.command == Command::Parse || self.options.emit.contains(&Emit::Ast) {
+            let _ = writeln!(io::stdout(), "{:#?}", parse.syntax());
+        }
+        for e in parse.errors() {
+            self.emit(Diagnostic::error(e.to_string()).with_span(e.span));
+        }
+    }
+
+    /// Rewrites the file in the canonical style, or with `--check` reports
+    /// it if that would change it. Files with syntax errors are left alone.
+    fn fmt(&mut self, id: FileId, path: &Path) {
+        let file = self.map.file(id);
+        let parse = yuri_parser::parse(Lexer::for_file(file), &self.interner);
+        let mut config = yuri_fmt::Config::default();
+        if let Some(width) = self.options.width {
+            config.width = width;
+        }
+        let formatted = yuri_fmt::format(&parse, &config).filter(|out| out != file.src());
+        for e in parse.errors() {
+            self.emit(Diagnostic::error(e.to_string()).with_span(e.span));
+        }
+        let formatted = match formatted {
+            Some(formatted) => formatted,
+            None => return,
+        };
+        if self.options.check {
+            self.emit(Diagnostic::error(format!(
+                "`{}` is not formatted",
+                path.display()
+            )));
+        } else if let Err(e) = fs::write(path, formatted) {
+            self.emit(Diagnostic::error(format!(
+                "cannot write {}: {}",
+                path.display(),
+                e
+            )));
+        }
+    }
+
+    /// Reports everything the command line asks for that the compiler cannot
+    /// do yet.
+    fn unsupported(&mut self) {
+        let command = match self.options.command {
+            Command::Check | Command::Fmt | Command::Lex | Command::Parse => None,
+            Command::Build => Some("build"),
+            Command::Run => Some("run"),
+            Command::Test => Some("test"),
+        };
+        if let Some(command) = command {
+            self.emit(Diagnostic::error(format!(
+                "`yuri {}` is not implemented yet",
+                command
+            )));
+        }
+        for emit in self.options.emit.clone() {
+            if emit > Emit::Ast {
+                self.emit(Diagnostic::error(format!(
+                    "`--emit {}` is not implemented yet",
+                    emit.as_str()
+                )));
+            }
+        }
+    }
+
+    fn summarize(&mut self) {
+        let warnings = self.emitter.warnings;
+        let errors = self.emitter.errors;
+        if warnings > 0 {
+            self.emit(Diagnostic::warning(match warnings {
+                1 => "1 warning emitted".to_string(),
+                n => format!("{} warnings emitted", n),
+            }));
+        }
+        if errors > 0 {
+            self.emit(Diagnostic::error(match errors {
+                1 => "aborting due to 1 previous error".to_string(),
+                n => format!("aborting due to {} previous errors", n),
+            }));
+        }
+    }
+}
+
+pub fn run(options: &Options) -> Outcome {
+    let mut session = Session {
+        options,
+        map: SourceMap::new(),
+        interner: RefCell::new(Interner::new()),
+        emitter: Emitter::new(io::stderr(), use_color(options.color)),
+    };
+    for (id, path) in session.read_inputs() {
+        session.lex(id);
+        match options.command {
+            Command::Lex => {}
+            Command::Fmt => session.fmt(id, path),
+            _ => session.parse(id),
+        }
+    }
+    session.unsupported();
+    let failed = session.emitter.errors > 0;
+    session.summarize();
+    if failed {
+        Outcome::Errors
+    } else {
+        Outcome::Success
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Format, OptLevel};
+
+    #[cfg(unix)]
+    #[test]
+    fn fmt_writes_to_a_path_that_is_not_utf8() {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = std::env::temp_dir().join(format!("yuri-fmt-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(OsStr::from_bytes(b"caf\xe9.yuri"));
+        fs::write(&path, "fn main(){}").unwrap();
+        let options = Options {
+            command: Command::Fmt,
+            inputs: vec![path.clone()],
+            emit: Vec::new(),
+            opt_level: OptLevel::O0,
+            target: None,
+            format: Format::Text,
+            color: ColorChoice::Never,
+            check: false,
+            width: None,
+        };
+        assert_eq!(run(&options), Outcome::Success);
+        let written = fs::read_to_string(&path).unwrap();
+        let files = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(written, "fn main () {}\n");
+        assert_eq!(files, 1);
+    }
+}
@@ -0,0 +1,195 @@
+//! Printing token streams for `yuri lex` and `--emit tokens`.
+
+use std::fmt::Write;
+
+use yuri_lexer::{Base, DocStyle, LexError, SourceFile, Token, TokenKind};
+
+use crate::cli::Format;
+
+pub fn dump(file: &SourceFile, tokens: &[Token], errors: &[LexError], format: Format) -> String {
+    match format {
+        Format::Text => dump_text(file, tokens),
+        Format::Json => dump_json(file, tokens, errors),
+    }
+}
+
+/// A payload of a token kind, such as the base of an integer literal.
+enum Value {
+    Str(&'static str),
+    Num(u8),
+}
+
+/// The payload of `kind` as named fields, in a fixed order. Absent suffixes
+/// are left out.
+fn fields(kind: TokenKind) -> Vec<(&'static str, Value)> {
+    use TokenKind::*;
+    match kind {
+        Keyword(keyword) => vec![("keyword", Value::Str(keyword.as_str()))],
+        DocComment(style) => {
+            let style = match style {
+                DocStyle::Outer => "outer",
+                DocStyle::Inner => "inner",
+            };
+            vec![("style", Value::Str(style))]
+        }
+        Int { base, suffix } => {
+            let base = match base {
+                Base::Binary => "binary",
+                Base::Octal => "octal",
+                Base::Decimal => "decimal",
+                Base::Hexadecimal => "hexadecimal",
+            };
+            let mut fields = vec![("base", Value::Str(base))];
+            fields.extend(suffix.map(|ty| ("suffix", Value::Str(ty.as_str()))));
+            fields
+        }
+        Float { suffix } => suffix
+            .map(|ty| ("suffix", Value::Str(ty.as_str())))
+            .into_iter()
+            .collect(),
+        Duration(unit) => vec![("unit", Value::Str(unit.as_str()))],
+        Size(unit) => vec![("unit", Value::Str(unit.as_str()))],
+        RawStr { hashes } | RawByteStr { hashes } => vec![("hashes", Value::Num(hashes))],
+        _ => Vec::new(),
+    }
+}
+
+/// One token per line: `line:col-line:col Kind field=value... "text"`.
+fn dump_text(file: &SourceFile, tokens: &[Token]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        let (start, end) = (file.line_col(token.span.lo), file.line_col(token.span.hi));
+        write!(
+            out,
+            "{}:{}-{}:{} {}",
+            start.line,
+            start.col,
+            end.line,
+            end.col,
+            token.kind.name()
+        )
+        .unwrap();
+        for (key, value) in fields(token.kind) {
+            match value {
+                Value::Str(s) => write!(out, " {}={}", key, s).unwrap(),
+                Value::Num(n) => write!(out, " {}={}", key, n).unwrap(),
+            }
+        }
+        writeln!(out, " {:?}", token.text).unwrap();
+    }
+    out
+}
+
+/// `{"tokens": [...], "errors": [...]}` with 1-based lines and UTF-8 columns,
+/// plus byte offsets.
+fn dump_json(file: &SourceFile, tokens: &[Token], errors: &[LexError]) -> String {
+    let range = |lo: u32, hi: u32| {
+        let (start, end) = (file.line_col(lo), file.line_col(hi));
+        format!(
+            r#""start":{{"line":{},"col":{},"offset":{}}},"end":{{"line":{},"col":{},"offset":{}}}"#,
+            start.line, start.col, lo, end.line, end.col, hi
+        )
+    };
+    let tokens: Vec<_> = tokens
+        .iter()
+        .map(|t| {
+            let mut payload = String::new();
+            for (key, value) in fields(t.kind) {
+                match value {
+                    Value::Str(s) => write!(payload, r#""{}":{},"#, key, json_str(s)).unwrap(),
+                    Value::Num(n) => write!(payload, r#""{}":{},"#, key, n).unwrap(),
+                }
+            }
+            format!(
+                r#"{{"kind":{},{}"text":{},{}}}"#,
+                json_str(t.kind.name()),
+                payload,
+                json_str(t.text),
+                range(t.span.lo, t.span.hi)
+            )
+        })
+        .collect();
+    let errors: Vec<_> = errors
+        .iter()
+        .map(|e| {
+            format!(
+                r#"{{"message":{},{}}}"#,
+                json_str(&e.to_string()),
+                range(e.span.lo, e.span.hi)
+            )
+        })
+        .collect();
+    format!(
+        "{{\"tokens\":[\n{}\n],\"errors\":[{}]}}\n",
+        tokens.join(",\n"),
+        errors.join(",")
+    )
+}
+
+fn json_str(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use yuri_lexer::{tokenize_with_errors, FileId};
+
+    #[test]
+    fn dumps() {
+        let file = SourceFile::new(FileId(0), "a.yuri", "let s =\n\"\\n\";");
+        let (tokens, errors) = tokenize_with_errors(file.src());
+        assert_eq!(
+            dump_text(&file, &tokens[..2]),
+            "1:1-1:4 Keyword keyword=let \"let\"\n1:5-1:6 Ident \"s\"\n"
+        );
+        let json = dump_json(&file, &tokens[3..4], &errors);
+        assert_eq!(
+            json,
+            concat!(
+                "{\"tokens\":[\n",
+                r#"{"kind":"Str","text":"\"\\n\"","start":{"line":2,"col":1,"offset":8},"#,
+                r#""end":{"line":2,"col":5,"offset":12}}"#,
+                "\n],\"errors\":[]}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn literal_fields() {
+        let file = SourceFile::new(FileId(0), "a.yuri", "0xffu8 1.5 250ms r#\"\"#");
+        let (tokens, _) = tokenize_with_errors(file.src());
+        assert_eq!(
+            dump_text(&file, &tokens[..3]),
+            concat!(
+                "1:1-1:7 Int base=hexadecimal suffix=u8 \"0xffu8\"\n",
+                "1:8-1:11 Float \"1.5\"\n",
+                "1:12-1:17 Duration unit=ms \"250ms\"\n",
+            )
+        );
+        assert_eq!(
+            dump_json(&file, &tokens[..1], &[]),
+            concat!(
+                "{\"tokens\":[\n",
+                r#"{"kind":"Int","base":"hexadecimal","suffix":"u8","text":"0xffu8","#,
+                r#""start":{"line":1,"col":1,"offset":0},"end":{"line":1,"col":7,"offset":6}}"#,
+                "\n],\"errors\":[]}\n"
+            )
+        );
+        assert!(dump_json(&file, &tokens[3..4], &[]).contains(r#""kind":"RawStr","hashes":1,"#));
+    }
+}
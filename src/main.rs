@@ -1,3 +1,57 @@
-fn main() {
-    println!("Hello, world!");
+use std::panic;
+use std::process::ExitCode;
+
+mod cli;
+mod diagnostics;
+mod driver;
+mod lex;
+
+use cli::Invocation;
+use driver::Outcome;
+
+/// The program compiled, or did what was asked.
+const EXIT_SUCCESS: u8 = 0;
+/// The input has errors, which have been reported.
+const EXIT_ERRORS: u8 = 1;
+/// The command line is malformed.
+const EXIT_USAGE: u8 = 2;
+/// The compiler itself crashed.
+const EXIT_ICE: u8 = 101;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match cli::parse(&args) {
+        Ok(Invocation::Compile(options)) => options,
+        Ok(Invocation::Help) => {
+            print!("{}", cli::USAGE);
+            return ExitCode::from(EXIT_SUCCESS);
+        }
+        Ok(Invocation::Version) => {
+            println!("yuri {}", env!("CARGO_PKG_VERSION"));
+            return ExitCode::from(EXIT_SUCCESS);
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+
+    panic::set_hook(Box::new(|info| {
+        let location = info
+            .location()
+            .map_or(String::new(), |l| format!(" at {}:{}", l.file(), l.line()));
+        let message = info
+            .payload()
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| info.payload().downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown panic");
+        eprintln!("error: internal compiler error: {}{}", message, location);
+        eprintln!("note: this is a bug in yuri; please report it at https://github.com/pandaman64/yuri/issues");
+    }));
+    match panic::catch_unwind(|| driver::run(&options)) {
+        Ok(Outcome::Success) => ExitCode::from(EXIT_SUCCESS),
+        Ok(Outcome::Errors) => ExitCode::from(EXIT_ERRORS),
+        Err(_) => ExitCode::from(EXIT_ICE),
+    }
 }
@@ -0,0 +1,233 @@
+//! Documents in the style of Wadler's "A prettier printer".
+//!
+//! A document is text with places where a line may break. The breaks of a
+//! group are taken all or none: a group goes on one line if it and whatever
+//! follows it up to the next break fit in the width, and every break in it
+//! becomes a newline otherwise.
+
+use std::borrow::Cow;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Doc {
+    Nil,
+    /// Text without newlines.
+    Text(Cow<'static, str>),
+    /// A newline, or `flat` where the enclosing group is on one line.
+    Line {
+        flat: &'static str,
+    },
+    /// A newline even where the rest of the group would fit, e.g. after a
+    /// line comment. It breaks every group around it.
+    HardLine,
+    /// Text only where the enclosing group is broken, such as the trailing
+    /// comma of a list that is one item per line.
+    IfBreak(&'static str),
+    Concat(Vec<Doc>),
+    /// A document whose newlines are indented by that many more columns.
+    Nest(usize, Box<Doc>),
+    Group(Box<Doc>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+type Cmd<'d> = (usize, Mode, &'d Doc);
+
+/// How many columns `text` takes up. Every character counts as one.
+fn width(text: &str) -> isize {
+    text.chars().count() as isize
+}
+
+impl Doc {
+    pub fn text(text: impl Into<Cow<'static, str>>) -> Doc {
+        Doc::Text(text.into())
+    }
+
+    /// A break that is a space on one line.
+    pub fn line() -> Doc {
+        Doc::Line { flat: " " }
+    }
+
+    /// A break that is nothing on one line.
+    pub fn softline() -> Doc {
+        Doc::Line { flat: "" }
+    }
+
+    pub fn concat(docs: impl IntoIterator<Item = Doc>) -> Doc {
+        Doc::Concat(docs.into_iter().collect())
+    }
+
+    /// `docs` with `sep` between each two of them.
+    pub fn join(docs: impl IntoIterator<Item = Doc>, sep: Doc) -> Doc {
+        let mut out = Vec::new();
+        for (i, doc) in docs.into_iter().enumerate() {
+            if i > 0 {
+                out.push(sep.clone());
+            }
+            out.push(doc);
+        }
+        Doc::Concat(out)
+    }
+
+    pub fn nest(self, indent: usize) -> Doc {
+        Doc::Nest(indent, Box::new(self))
+    }
+
+    pub fn group(self) -> Doc {
+        Doc::Group(Box::new(self))
+    }
+
+    /// Lays the document out in `width` columns, going over only where some
+    /// text is too long to fit. Lines never end in spaces.
+    pub fn render(&self, width: usize) -> String {
+        let width = width as isize;
+        let mut out = String::new();
+        let mut column = 0;
+        let mut stack: Vec<Cmd> = vec![(0, Mode::Break, self)];
+        while let Some((indent, mode, doc)) = stack.pop() {
+            match doc {
+                Doc::Nil => {}
+                Doc::Text(text) => {
+                    out.push_str(text);
+                    column += self::width(text);
+                }
+                Doc::Line { flat } if mode == Mode::Flat => {
+                    out.push_str(flat);
+                    column += self::width(flat);
+                }
+                Doc::Line { .. } | Doc::HardLine => {
+                    trim_end(&mut out);
+                    out.push('\n');
+                    out.extend(std::iter::repeat_n(' ', indent));
+                    column = indent as isize;
+                }
+                Doc::IfBreak(text) => {
+                    if mode == Mode::Break {
+                        out.push_str(text);
+                        column += self::width(text);
+                    }
+                }
+                Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+                Doc::Nest(more, doc) => stack.push((indent + more, mode, doc)),
+                Doc::Group(doc) => {
+                    let flat = mode == Mode::Flat
+                        || fits(width - column, (indent, Mode::Flat, doc), &stack);
+                    let mode = if flat { Mode::Flat } else { Mode::Break };
+                    stack.push((indent, mode, doc));
+                }
+            }
+        }
+        trim_end(&mut out);
+        out
+    }
+}
+
+fn trim_end(out: &mut String) {
+    let len = out.trim_end_matches(' ').len();
+    out.truncate(len);
+}
+
+/// Whether `next` fits in `remaining` columns on one line along with the
+/// commands after it on `rest` up to their first newline.
+fn fits(mut remaining: isize, next: Cmd, rest: &[Cmd]) -> bool {
+    let mut stack = vec![next];
+    let mut rest = rest.iter().rev();
+    while remaining >= 0 {
+        let (indent, mode, doc) = match stack.pop().or_else(|| rest.next().copied()) {
+            Some(cmd) => cmd,
+            None => return true,
+        };
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(text) => remaining -= width(text),
+            Doc::Line { flat } => match mode {
+                Mode::Flat => remaining -= width(flat),
+                Mode::Break => return true,
+            },
+            Doc::HardLine => return mode == Mode::Break,
+            Doc::IfBreak(text) => {
+                if mode == Mode::Break {
+                    remaining -= width(text);
+                }
+            }
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|doc| (indent, mode, doc))),
+            Doc::Nest(more, doc) => stack.push((indent + more, mode, doc)),
+            Doc::Group(doc) => stack.push((indent, mode, doc)),
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `f(a, b)`, with the arguments one per line if it does not fit.
+    fn call(args: &[&'static str]) -> Doc {
+        Doc::concat(vec![
+            Doc::text("f("),
+            Doc::concat(vec![
+                Doc::softline(),
+                Doc::join(
+                    args.iter().map(|arg| Doc::text(*arg)),
+                    Doc::concat(vec![Doc::text(","), Doc::line()]),
+                ),
+                Doc::IfBreak(","),
+            ])
+            .nest(4),
+            Doc::softline(),
+            Doc::text(")"),
+        ])
+        .group()
+    }
+
+    #[test]
+    fn groups_break_all_or_nothing() {
+        let doc = call(&["alpha", "beta"]);
+        assert_eq!(doc.render(20), "f(alpha, beta)");
+        assert_eq!(doc.render(10), "f(\n    alpha,\n    beta,\n)");
+    }
+
+    #[test]
+    fn what_follows_a_group_counts() {
+        let doc = Doc::concat(vec![call(&["alpha"]), Doc::text(" + omega")]);
+        assert_eq!(doc.render(16), "f(alpha) + omega");
+        assert_eq!(doc.render(15), "f(\n    alpha,\n) + omega");
+    }
+
+    #[test]
+    fn hard_lines_break_groups() {
+        let doc = Doc::concat(vec![
+            Doc::text("{"),
+            Doc::concat(vec![Doc::line(), Doc::text("// note")]).nest(4),
+            Doc::HardLine,
+            Doc::text("}"),
+        ])
+        .group();
+        assert_eq!(doc.render(80), "{\n    // note\n}");
+    }
+
+    #[test]
+    fn nested_groups_break_outside_in() {
+        let doc = Doc::concat(vec![
+            Doc::text("g("),
+            Doc::concat(vec![
+                Doc::softline(),
+                call(&["x"]),
+                Doc::text(","),
+                Doc::line(),
+                call(&["y"]),
+                Doc::IfBreak(","),
+            ])
+            .nest(4),
+            Doc::softline(),
+            Doc::text(")"),
+        ])
+        .group();
+        assert_eq!(doc.render(80), "g(f(x), f(y))");
+        assert_eq!(doc.render(10), "g(\n    f(x),\n    f(y),\n)");
+    }
+}
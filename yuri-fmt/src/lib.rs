@@ -0,0 +1,265 @@
+//! The formatter behind `yuri fmt`, which prints a file in the canonical
+//! style from its concrete syntax tree, keeping every comment.
+
+mod doc;
+mod print;
+
+use yuri_lexer::TokenKind;
+use yuri_parser::syntax::NodeKind;
+use yuri_parser::Parse;
+
+pub use doc::Doc;
+
+use print::Printer;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// The number of columns that lines should fit in.
+    pub width: usize,
+    /// The number of spaces per level of indentation.
+    pub indent: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            width: 100,
+            indent: 4,
+        }
+    }
+}
+
+/// The formatted source of a file, or `None` if it has syntax errors, since
+/// the tree of a broken file does not say what the code should look like.
+pub fn format(parse: &Parse, config: &Config) -> Option<String> {
+    let root = parse.syntax();
+    if !parse.errors().is_empty()
+        || root
+            .descendants()
+            .any(|node| node.kind() == NodeKind::Error)
+        || root.tokens().any(|token| token.kind() == TokenKind::Error)
+    {
+        return None;
+    }
+    let printer = Printer {
+        indent: config.indent,
+    };
+    let text = printer.source_file(&root).render(config.width);
+    let text = text.trim_start_matches('\n');
+    if text.is_empty() {
+        Some(String::new())
+    } else {
+        Some(format!("{}\n", text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::path::Path;
+    use std::{env, fs};
+
+    use yuri_lexer::{Interner, Lexer};
+
+    use super::*;
+
+    fn parse(src: &str) -> Parse {
+        yuri_parser::parse(Lexer::new(src), &RefCell::new(Interner::new()))
+    }
+
+    fn fmt_width(src: &str, width: usize) -> Option<String> {
+        let config = Config {
+            width,
+            ..Config::default()
+        };
+        format(&parse(src), &config)
+    }
+
+    /// The tokens and comments of `src`, except for the commas and `|` that
+    /// formatting may add or remove.
+    fn tokens(src: &str) -> Vec<String> {
+        parse(src)
+            .syntax()
+            .tokens()
+            .filter(|token| {
+                !matches!(
+                    token.kind(),
+                    TokenKind::Whitespace | TokenKind::Comma | TokenKind::Pipe
+                )
+            })
+            .map(|token| token.text().trim_end().to_string())
+            .collect()
+    }
+
+    /// Formats `src`, checking that it keeps every token and comment and
+    /// that formatting the result again changes nothing.
+    fn fmt(src: &str) -> String {
+        let out = fmt_width(src, 100).expect("syntax error");
+        assert_eq!(tokens(&out), tokens(src), "tokens changed");
+        assert_eq!(
+            fmt_width(&out, 100).as_deref(),
+            Some(&*out),
+            "not idempotent"
+        );
+        out
+    }
+
+    #[test]
+    fn items() {
+        assert_eq!(
+            fmt("pub  fn f<T:A+B>(a:T)(b:&'a mut[u8])  :Vec<Vec<T>>{a}"),
+            "pub fn f<T: A + B> (a: T) (b: &'a mut [u8]): Vec<Vec<T>> {\n    a\n}\n",
+        );
+        assert_eq!(
+            fmt("fn f<'a,'b:'a+'static,T:'a+Send,const N:usize>(x:&'a T){}"),
+            "fn f<'a, 'b: 'a + 'static, T: 'a + Send, const N: usize> (x: &'a T) {}\n",
+        );
+        assert_eq!(
+            fmt("fn f(g:fn(&[u8])(&&T):Buf<{N}>){}"),
+            "fn f (g: fn (&[u8]) (&&T): Buf<{ N }>) {}\n",
+        );
+        assert_eq!(
+            fmt("struct P{x:u8,pub(crate) y:[u8;4]}struct T(u8,u16);enum E{A,B(u8),C{x:u8}}"),
+            "struct P {\n    x: u8,\n    pub(crate) y: [u8; 4],\n}\nstruct T(u8, u16);\n\
+             enum E {\n    A,\n    B(u8),\n    C { x: u8 },\n}\n",
+        );
+        assert_eq!(
+            fmt("use a::{b,c as d,e::*};mod m;impl<T>Tr for S<T>{fn f(self){}}"),
+            "use a::{b, c as d, e::*};\nmod m;\nimpl<T> Tr for S<T> {\n    fn f (self) {}\n}\n",
+        );
+    }
+
+    #[test]
+    fn expressions() {
+        assert_eq!(
+            fmt("fn f(){let x:u8=-a+b*c;x+=1;let t=(a,);g(&mut x,&&y,!z,*p)[0..n].h::<u8>(x as u16);}"),
+            "fn f () {\n    let x: u8 = -a + b * c;\n    x += 1;\n    let t = (a,);\n    \
+             g(&mut x, &&y, !z, *p)[0..n].h::<u8>(x as u16);\n}\n",
+        );
+        assert_eq!(
+            fmt("fn f(){if let Some(x)=it.next(){break x}else if a{return}else{S{a,b:1}}}"),
+            "fn f () {\n    if let Some(x) = it.next() {\n        break x\n    } else if a {\n        \
+             return\n    } else {\n        S { a, b: 1 }\n    }\n}\n",
+        );
+        assert_eq!(
+            fmt("fn f(){match x{|A|B=>{},ref mut y@1..=9=>y,[a,..]=>{a}}}"),
+            "fn f () {\n    match x {\n        A | B => {}\n        ref mut y @ 1..=9 => y,\n        \
+             [a, ..] => {\n            a\n        }\n    }\n}\n",
+        );
+        assert_eq!(
+            fmt("fn f(){match x{1|2 if y=>{},Some(n)if n>0=>n,_=>()}}"),
+            "fn f () {\n    match x {\n        1 | 2 if y => {}\n        Some(n) if n > 0 => n,\n        \
+             _ => (),\n    }\n}\n",
+        );
+        assert_eq!(
+            fmt("fn f(){let n=read(p) ?.len()?;g(x?)?}"),
+            "fn f () {\n    let n = read(p)?.len()?;\n    g(x?)?\n}\n",
+        );
+    }
+
+    #[test]
+    fn long_lines_break() {
+        assert_eq!(
+            fmt_width("fn f(){g(alpha,beta,gamma)}", 16).unwrap(),
+            "fn f () {\n    g(\n        alpha,\n        beta,\n        gamma,\n    )\n}\n",
+        );
+        assert_eq!(
+            fmt_width("fn f(){alpha+beta}", 12).unwrap(),
+            "fn f () {\n    alpha\n        + beta\n}\n",
+        );
+    }
+
+    #[test]
+    fn comments() {
+        assert_eq!(
+            fmt("//! Docs.\n\n\n/// An item.\nfn f(){ // why\n  x; /* same */\n\n\n  // next\n  y }\n// end\n"),
+            "//! Docs.\n\n/// An item.\nfn f () { // why\n    x; /* same */\n\n    // next\n    y\n}\n// end\n",
+        );
+        assert_eq!(
+            fmt("enum E{A,// a\nB}fn f(){g(a,// first\nb)}"),
+            "enum E {\n    A, // a\n    B,\n}\nfn f () {\n    g(\n        a, // first\n        b,\n    )\n}\n",
+        );
+        assert_eq!(
+            fmt("fn f(){let x=/* one */1;}"),
+            "fn f () {\n    let x = /* one */ 1;\n}\n"
+        );
+        assert_eq!(
+            fmt("fn f(){g(a /* inner */,b/* last */)}"),
+            "fn f () {\n    g(a /* inner */, b /* last */)\n}\n"
+        );
+        assert_eq!(
+            fmt("struct S { a: u8, /* c */ b: u8 }"),
+            "struct S {\n    a: u8,\n    /* c */ b: u8,\n}\n"
+        );
+        assert_eq!(
+            fmt("fn f(){g(/* first */ a, /* second */ b)}"),
+            "fn f () {\n    g(/* first */ a, /* second */ b)\n}\n"
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(fmt_width("fn f() { let x = $; }", 100), None);
+        assert_eq!(fmt_width("fn f(", 100), None);
+        assert_eq!(fmt_width("", 100).as_deref(), Some(""));
+    }
+
+    /// The code of each `rust` block of `markdown`.
+    fn snippets(markdown: &str) -> Vec<String> {
+        let mut snippets = Vec::new();
+        let mut snippet: Option<String> = None;
+        for line in markdown.lines() {
+            match &mut snippet {
+                None if line == "```rust" => snippet = Some(String::new()),
+                Some(_) if line == "```" => snippets.extend(snippet.take()),
+                Some(snippet) => {
+                    snippet.push_str(line);
+                    snippet.push('\n');
+                }
+                None => {}
+            }
+        }
+        snippets
+    }
+
+    /// Makes a file of a snippet: the `...` lines that stand for omitted
+    /// code become comments, and a snippet of bare statements becomes the
+    /// body of `fn main`.
+    fn standalone(snippet: &str) -> String {
+        let src: String = snippet
+            .lines()
+            .map(|line| match line.trim_start() {
+                "..." => format!("{}// ...\n", &line[..line.len() - 3]),
+                _ => format!("{}\n", line),
+            })
+            .collect();
+        if parse(&src).errors().is_empty() {
+            src
+        } else {
+            format!("fn main () {{\n{}}}\n", src)
+        }
+    }
+
+    /// Formats each `rust` snippet of DESIGN.md and compares the result with
+    /// the files in `golden`. Run with `BLESS=1` to write them instead.
+    #[test]
+    fn design_snippets() {
+        let design = include_str!("../../DESIGN.md");
+        let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("golden");
+        for (i, snippet) in snippets(design).iter().enumerate() {
+            let src = standalone(snippet);
+            let name = format!("design-{}.yuri", i + 1);
+            let errors = parse(&src).errors().to_vec();
+            assert!(errors.is_empty(), "{} does not parse: {:?}", name, errors);
+            let out = fmt(&src);
+            let path = golden.join(&name);
+            if env::var_os("BLESS").is_some() {
+                fs::create_dir_all(&golden).unwrap();
+                fs::write(&path, &out).unwrap();
+            } else {
+                let expected = fs::read_to_string(&path).unwrap_or_default();
+                assert_eq!(out, expected, "{} differs; rerun with BLESS=1", name);
+            }
+        }
+    }
+}
@@ -0,0 +1,637 @@
+//! Turning the concrete syntax tree of a file into a document.
+//!
+//! Every token and comment of a node is printed in order, so nothing of the
+//! source is lost. Whitespace is dropped and the layout comes from the kinds
+//! of the neighbouring tokens and nodes instead, except that a single blank
+//! line between items, statements, fields or arms is kept.
+
+use std::collections::VecDeque;
+use std::mem;
+
+use yuri_lexer::{Keyword, TokenKind};
+use yuri_parser::syntax::{NodeKind, SyntaxElement, SyntaxNode};
+
+use crate::doc::Doc;
+
+/// A comment and where it sits relative to the code around it.
+struct Comment {
+    text: String,
+    /// Whether the comment starts its line rather than following code.
+    own_line: bool,
+    /// Whether a blank line comes right before it.
+    blank_before: bool,
+    /// Whether a line break has to follow it, as after every line comment.
+    ends_line: bool,
+}
+
+/// A child of a node other than trivia, with the comments before it.
+struct Part {
+    comments: Vec<Comment>,
+    blank_before: bool,
+    elem: SyntaxElement,
+}
+
+impl Part {
+    fn token(&self) -> Option<TokenKind> {
+        match &self.elem {
+            SyntaxElement::Token(token) => Some(token.kind()),
+            SyntaxElement::Node(_) => None,
+        }
+    }
+}
+
+/// The parts of a node, and the comments after the last of them.
+struct Parts {
+    parts: VecDeque<Part>,
+    trailing: Vec<Comment>,
+}
+
+impl Parts {
+    fn new(node: &SyntaxNode) -> Parts {
+        let mut parts = VecDeque::new();
+        let mut comments: Vec<Comment> = Vec::new();
+        let mut newlines = 0;
+        let mut at_start = true;
+        for elem in node.children_with_tokens() {
+            let kind = match &elem {
+                SyntaxElement::Token(token) => Some(token.kind()),
+                SyntaxElement::Node(_) => None,
+            };
+            match kind {
+                Some(TokenKind::Whitespace) => {
+                    let text = match &elem {
+                        SyntaxElement::Token(token) => token.text(),
+                        SyntaxElement::Node(_) => unreachable!(),
+                    };
+                    let lines = text.matches('\n').count();
+                    if lines > 0 && newlines == 0 {
+                        if let Some(comment) = comments.last_mut() {
+                            comment.ends_line = true;
+                        }
+                    }
+                    newlines += lines;
+                }
+                Some(kind @ TokenKind::LineComment)
+                | Some(kind @ TokenKind::BlockComment)
+                | Some(kind @ TokenKind::DocComment(_)) => {
+                    let text = match &elem {
+                        SyntaxElement::Token(token) => token.text().trim_end().to_string(),
+                        SyntaxElement::Node(_) => unreachable!(),
+                    };
+                    comments.push(Comment {
+                        text,
+                        own_line: at_start || newlines > 0,
+                        blank_before: newlines > 1,
+                        ends_line: kind != TokenKind::BlockComment,
+                    });
+                    newlines = 0;
+                    at_start = false;
+                }
+                _ => {
+                    parts.push_back(Part {
+                        comments: mem::take(&mut comments),
+                        blank_before: newlines > 1,
+                        elem,
+                    });
+                    newlines = 0;
+                    at_start = false;
+                }
+            }
+        }
+        Parts {
+            parts,
+            trailing: comments,
+        }
+    }
+
+    fn at(&self, kind: TokenKind) -> bool {
+        self.parts.front().and_then(Part::token) == Some(kind)
+    }
+
+    fn next(&mut self) -> Option<Part> {
+        self.parts.pop_front()
+    }
+}
+
+/// How a part was printed, which decides the space around it.
+enum Atom {
+    Token(TokenKind),
+    Node(SyntaxNode),
+    /// A delimited list, by its opening token.
+    List(TokenKind),
+}
+
+/// Whether a list keeps a comma after its last entry.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Trailing {
+    Never,
+    /// Only where the list is one entry per line.
+    IfBroken,
+    Always,
+}
+
+/// An entry of a comma-separated list.
+struct Entry {
+    comments: Vec<Comment>,
+    blank_before: bool,
+    doc: Doc,
+    /// The comments between the entry and its comma.
+    after: Vec<Comment>,
+    /// Whether this is a `match` arm whose body is a block.
+    block_arm: bool,
+}
+
+/// Lines that each start with a newline, as in a block or a source file.
+#[derive(Default)]
+struct Lines {
+    docs: Vec<Doc>,
+}
+
+impl Lines {
+    /// Own-line comments go on lines of their own and the others go at the
+    /// end of the current line.
+    fn comments(&mut self, comments: Vec<Comment>) {
+        for comment in comments {
+            if comment.own_line {
+                self.newline(comment.blank_before);
+            } else {
+                self.docs.push(Doc::text(" "));
+            }
+            self.docs.push(Doc::text(comment.text));
+        }
+    }
+
+    fn newline(&mut self, blank: bool) {
+        if blank && !self.docs.is_empty() {
+            self.docs.push(Doc::HardLine);
+        }
+        self.docs.push(Doc::HardLine);
+    }
+
+    fn line(&mut self, comments: Vec<Comment>, blank_before: bool, doc: Doc) {
+        self.comments(comments);
+        self.newline(blank_before);
+        self.docs.push(doc);
+    }
+
+    /// The lines indented between `open` and `close`, or `{}` without any.
+    fn wrap(self, open: &'static str, close: &'static str, indent: usize) -> Doc {
+        if self.docs.is_empty() {
+            return Doc::concat(vec![Doc::text(open), Doc::text(close)]);
+        }
+        Doc::concat(vec![
+            Doc::text(open),
+            Doc::Concat(self.docs).nest(indent),
+            Doc::HardLine,
+            Doc::text(close),
+        ])
+    }
+}
+
+pub(crate) struct Printer {
+    pub(crate) indent: usize,
+}
+
+impl Printer {
+    /// The items of a file, without the newline at the end.
+    pub(crate) fn source_file(&self, node: &SyntaxNode) -> Doc {
+        let mut parts = Parts::new(node);
+        let lines = self.lines(&mut parts, None);
+        Doc::Concat(lines.docs)
+    }
+
+    /// Parts one per line up to `close`, which is consumed.
+    fn lines(&self, parts: &mut Parts, close: Option<TokenKind>) -> Lines {
+        let mut lines = Lines::default();
+        while let Some(part) = parts.next() {
+            if part.token().is_some() && part.token() == close {
+                lines.comments(part.comments);
+                return lines;
+            }
+            let doc = self.elem(&part.elem);
+            lines.line(part.comments, part.blank_before, doc);
+        }
+        lines.comments(mem::take(&mut parts.trailing));
+        lines
+    }
+
+    fn elem(&self, elem: &SyntaxElement) -> Doc {
+        match elem {
+            SyntaxElement::Token(token) => Doc::text(token.text().to_string()),
+            SyntaxElement::Node(node) => self.node(node),
+        }
+    }
+
+    fn node(&self, node: &SyntaxNode) -> Doc {
+        let kind = node.kind();
+        let mut parts = Parts::new(node);
+        // A block as a generic argument, as in `Buf<{ N }>`, stays on one
+        // line like the other arguments.
+        let in_generic_args =
+            node.parent().map(|parent| parent.kind()) == Some(NodeKind::GenericArgs);
+        if kind == NodeKind::Block && !in_generic_args {
+            parts.next();
+            return self
+                .lines(&mut parts, Some(TokenKind::BraceClose))
+                .wrap("{", "}", self.indent);
+        }
+        let atoms = self.atoms(kind, &mut parts, &[]);
+        self.join(kind, atoms)
+    }
+
+    /// Prints parts up to one of the tokens in `until` or the end of the
+    /// node, taking delimited lists as a whole.
+    fn atoms(&self, parent: NodeKind, parts: &mut Parts, until: &[TokenKind]) -> Vec<(Atom, Doc)> {
+        let mut atoms = Vec::new();
+        let mut comments = Vec::new();
+        while let Some(part) = parts.parts.front() {
+            let kind = part.token();
+            if kind.is_some_and(|kind| until.contains(&kind)) {
+                break;
+            }
+            let mut part = parts.next().unwrap();
+            comments.append(&mut part.comments);
+            // The `|` before the first alternative of an arm is left out.
+            if parent == NodeKind::Arm && atoms.is_empty() && kind == Some(TokenKind::Pipe) {
+                continue;
+            }
+            part.comments = mem::take(&mut comments);
+            atoms.push(self.atom(parent, part, parts));
+        }
+        // The comments of a `|` left out with nothing after it go with what
+        // comes next instead.
+        if !comments.is_empty() {
+            let next = match parts.parts.front_mut() {
+                Some(part) => &mut part.comments,
+                None => &mut parts.trailing,
+            };
+            comments.append(next);
+            *next = comments;
+        }
+        atoms
+    }
+
+    /// Prints `part`, along with the rest of the list if it opens one.
+    fn atom(&self, parent: NodeKind, part: Part, parts: &mut Parts) -> (Atom, Doc) {
+        let (atom, doc) = match (part.token(), &part.elem) {
+            (Some(TokenKind::BraceOpen), _)
+                if matches!(parent, NodeKind::Impl | NodeKind::Trait | NodeKind::Mod) =>
+            {
+                let lines = self.lines(parts, Some(TokenKind::BraceClose));
+                (
+                    Atom::List(TokenKind::BraceOpen),
+                    lines.wrap("{", "}", self.indent),
+                )
+            }
+            (Some(open), _) if closing(parent, open).is_some() => {
+                (Atom::List(open), self.list(parent, parts, open))
+            }
+            (Some(kind), elem) => (Atom::Token(kind), self.elem(elem)),
+            (None, SyntaxElement::Node(node)) => (Atom::Node(node.clone()), self.node(node)),
+            (None, SyntaxElement::Token(_)) => unreachable!(),
+        };
+        (atom, with_comments(part.comments, doc))
+    }
+
+    /// Puts spaces between atoms where their kinds call for them. The
+    /// operator of a binary expression may start a new line instead.
+    fn join(&self, parent: NodeKind, atoms: Vec<(Atom, Doc)>) -> Doc {
+        if parent == NodeKind::BinaryExpr && atoms.len() == 3 {
+            let mut docs = atoms.into_iter().map(|(_, doc)| doc);
+            let (lhs, op, rhs) = (
+                docs.next().unwrap(),
+                docs.next().unwrap(),
+                docs.next().unwrap(),
+            );
+            return Doc::concat(vec![
+                lhs,
+                Doc::concat(vec![Doc::line(), op, Doc::text(" "), rhs]).nest(self.indent),
+            ])
+            .group();
+        }
+        let mut docs = Vec::new();
+        let mut prev: Option<Atom> = None;
+        for (atom, doc) in atoms {
+            if let Some(prev) = &prev {
+                if space(parent, prev, &atom) {
+                    docs.push(Doc::text(" "));
+                }
+            }
+            docs.push(doc);
+            prev = Some(atom);
+        }
+        Doc::Concat(docs)
+    }
+
+    /// A list after its opening token `open`, up to and including the token
+    /// that closes it.
+    fn list(&self, parent: NodeKind, parts: &mut Parts, open: TokenKind) -> Doc {
+        let close = closing(parent, open).unwrap();
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut comma = false;
+        let trailing = loop {
+            let part = match parts.parts.front_mut() {
+                Some(part) => part,
+                None => break mem::take(&mut parts.trailing),
+            };
+            if part.token() == Some(close) {
+                break parts.next().unwrap().comments;
+            }
+            let mut comments = mem::take(&mut part.comments);
+            let blank_before = part.blank_before;
+            // An arm whose body is a block needs no comma after it.
+            let atoms = if parent == NodeKind::MatchExpr {
+                let part = parts.next().unwrap();
+                vec![self.atom(parent, part, parts)]
+            } else {
+                self.atoms(parent, parts, &[TokenKind::Comma, close])
+            };
+            let block_arm = match atoms.as_slice() {
+                [(Atom::Node(arm), _)] if arm.kind() == NodeKind::Arm => {
+                    arm.children().last().map(|body| body.kind()) == Some(NodeKind::Block)
+                }
+                _ => false,
+            };
+            let mut doc = with_leading_inline_comments(&mut comments, self.join(parent, atoms));
+            comma = parts.at(TokenKind::Comma);
+            let mut after = if comma {
+                parts.next().unwrap().comments
+            } else if parts.at(close) {
+                mem::take(&mut parts.parts[0].comments)
+            } else {
+                Vec::new()
+            };
+            doc = with_inline_comments(doc, &mut after);
+            entries.push(Entry {
+                comments,
+                blank_before,
+                doc,
+                after,
+                block_arm,
+            });
+        };
+        let (open, close) = (text(open), text(close));
+        let trailing_comma = match parent {
+            NodeKind::TuplePat | NodeKind::TupleTy | NodeKind::TupleExpr if entries.len() == 1 => {
+                if comma {
+                    Trailing::Always
+                } else {
+                    Trailing::Never
+                }
+            }
+            _ if is_comma_list(parent) => Trailing::IfBroken,
+            _ => Trailing::Never,
+        };
+        let vertical = (matches!(
+            parent,
+            NodeKind::Struct | NodeKind::Enum | NodeKind::MatchExpr
+        ) && open == "{")
+            || !trailing.is_empty()
+            || entries
+                .iter()
+                .any(|entry| !entry.comments.is_empty() || !entry.after.is_empty());
+        if vertical {
+            let mut lines = Lines::default();
+            for entry in entries {
+                let comma = match trailing_comma {
+                    Trailing::Never => false,
+                    _ => !entry.block_arm,
+                };
+                let doc = if comma {
+                    Doc::concat(vec![entry.doc, Doc::text(",")])
+                } else {
+                    entry.doc
+                };
+                lines.line(entry.comments, entry.blank_before, doc);
+                lines.comments(entry.after);
+            }
+            lines.comments(trailing);
+            return lines.wrap(open, close, self.indent);
+        }
+        if entries.is_empty() {
+            return Doc::concat(vec![Doc::text(open), Doc::text(close)]);
+        }
+        let pad = if open == "{" && parent != NodeKind::UseTree {
+            Doc::line()
+        } else {
+            Doc::softline()
+        };
+        let last = match trailing_comma {
+            Trailing::Never => Doc::Nil,
+            Trailing::IfBroken => Doc::IfBreak(","),
+            Trailing::Always => Doc::text(","),
+        };
+        Doc::concat(vec![
+            Doc::text(open),
+            Doc::concat(vec![
+                pad.clone(),
+                Doc::join(
+                    entries.into_iter().map(|entry| entry.doc),
+                    Doc::concat(vec![Doc::text(","), Doc::line()]),
+                ),
+                last,
+            ])
+            .nest(self.indent),
+            pad,
+            Doc::text(close),
+        ])
+        .group()
+    }
+}
+
+/// `doc` after the comments before it, each followed by a space or, if it
+/// has to end its line, by a newline.
+fn with_comments(comments: Vec<Comment>, doc: Doc) -> Doc {
+    if comments.is_empty() {
+        return doc;
+    }
+    let mut docs = Vec::new();
+    for comment in comments {
+        let ends_line = comment.ends_line;
+        docs.push(Doc::text(comment.text));
+        docs.push(if ends_line {
+            Doc::HardLine
+        } else {
+            Doc::text(" ")
+        });
+    }
+    docs.push(doc);
+    Doc::Concat(docs)
+}
+
+/// `doc` after the block comments at the end of `comments` that share its
+/// line, as in `a, /* why */ b`, so that they stay with it instead of going
+/// after the comma before it. The rest are left in `comments`.
+fn with_leading_inline_comments(comments: &mut Vec<Comment>, doc: Doc) -> Doc {
+    let inline = comments
+        .iter()
+        .rev()
+        .take_while(|comment| !comment.ends_line)
+        .count();
+    let inline = comments.split_off(comments.len() - inline);
+    with_comments(inline, doc)
+}
+
+/// `doc` followed by the block comments at the start of `comments` that
+/// share its line, as in `a /* why */, b`, so that they stay with it
+/// without breaking the list. The rest are left in `comments`.
+fn with_inline_comments(doc: Doc, comments: &mut Vec<Comment>) -> Doc {
+    let inline = comments
+        .iter()
+        .take_while(|comment| !comment.own_line && !comment.ends_line)
+        .count();
+    if inline == 0 {
+        return doc;
+    }
+    let mut docs = vec![doc];
+    for comment in comments.drain(..inline) {
+        docs.push(Doc::text(" "));
+        docs.push(Doc::text(comment.text));
+    }
+    Doc::Concat(docs)
+}
+
+/// The token that closes a list opened by `open` within a `parent` node, if
+/// `open` opens one there.
+fn closing(parent: NodeKind, open: TokenKind) -> Option<TokenKind> {
+    match open {
+        TokenKind::ParenOpen => Some(TokenKind::ParenClose),
+        TokenKind::BracketOpen => Some(TokenKind::BracketClose),
+        TokenKind::BraceOpen => Some(TokenKind::BraceClose),
+        // Elsewhere `<` is the less-than operator.
+        TokenKind::Less if parent != NodeKind::BinaryExpr => Some(TokenKind::Greater),
+        _ => None,
+    }
+}
+
+fn text(kind: TokenKind) -> &'static str {
+    match kind {
+        TokenKind::ParenOpen => "(",
+        TokenKind::ParenClose => ")",
+        TokenKind::BraceOpen => "{",
+        TokenKind::BraceClose => "}",
+        TokenKind::BracketOpen => "[",
+        TokenKind::BracketClose => "]",
+        TokenKind::Less => "<",
+        TokenKind::Greater => ">",
+        _ => unreachable!(),
+    }
+}
+
+/// Whether the lists within `kind` are separated by commas, unlike the
+/// brackets of an array type or the parentheses around an expression.
+fn is_comma_list(kind: NodeKind) -> bool {
+    kind.is_item()
+        || matches!(
+            kind,
+            NodeKind::ParamList
+                | NodeKind::Variant
+                | NodeKind::UseTree
+                | NodeKind::GenericArgs
+                | NodeKind::TupleTy
+                | NodeKind::FnTy
+                | NodeKind::TupleStructPat
+                | NodeKind::StructPat
+                | NodeKind::TuplePat
+                | NodeKind::SlicePat
+                | NodeKind::TupleExpr
+                | NodeKind::ArrayExpr
+                | NodeKind::StructExpr
+                | NodeKind::CallExpr
+                | NodeKind::MethodCallExpr
+                | NodeKind::MatchExpr
+        )
+}
+
+/// Whether `kind` is an operator that goes between two operands.
+fn is_infix(kind: TokenKind) -> bool {
+    use TokenKind::*;
+    matches!(
+        kind,
+        Arrow
+            | FatArrow
+            | At
+            | Equal
+            | EqualEqual
+            | BangEqual
+            | Less
+            | LessEqual
+            | Greater
+            | GreaterEqual
+            | Plus
+            | PlusEqual
+            | Minus
+            | MinusEqual
+            | Star
+            | StarEqual
+            | Slash
+            | SlashEqual
+            | Percent
+            | PercentEqual
+            | Caret
+            | CaretEqual
+            | Amp
+            | AmpAmp
+            | AmpEqual
+            | Pipe
+            | PipePipe
+            | PipeEqual
+            | LessLess
+            | LessLessEqual
+            | GreaterGreater
+            | GreaterGreaterEqual
+    )
+}
+
+/// Whether a space goes between `prev` and `next` within a `parent` node.
+fn space(parent: NodeKind, prev: &Atom, next: &Atom) -> bool {
+    // Within these nodes `-`, `*` and `&` are prefixes, as in `-1`, `*p`
+    // or `&mut x`.
+    let prefix = matches!(
+        parent,
+        NodeKind::UnaryExpr
+            | NodeKind::RefExpr
+            | NodeKind::RefTy
+            | NodeKind::BindingPat
+            | NodeKind::LitExpr
+            | NodeKind::LitPat
+            | NodeKind::SelfParam
+    );
+    match (prev, next) {
+        (
+            _,
+            Atom::Token(
+                TokenKind::Comma
+                | TokenKind::Semicolon
+                | TokenKind::Colon
+                | TokenKind::ColonColon
+                | TokenKind::Dot
+                | TokenKind::Question,
+            ),
+        ) => false,
+        (Atom::Token(TokenKind::ColonColon | TokenKind::Dot | TokenKind::Bang), _) => false,
+        (Atom::Token(TokenKind::DotDot | TokenKind::DotDotEqual), _)
+        | (_, Atom::Token(TokenKind::DotDot | TokenKind::DotDotEqual)) => false,
+        (Atom::Token(TokenKind::Colon | TokenKind::Semicolon | TokenKind::Comma), _) => true,
+        (Atom::Token(kind), _) if is_infix(*kind) => {
+            !(prefix
+                && matches!(
+                    kind,
+                    TokenKind::Minus | TokenKind::Star | TokenKind::Amp | TokenKind::AmpAmp
+                ))
+        }
+        (_, Atom::Token(kind)) if is_infix(*kind) => true,
+        // `impl<T>` and `pub(crate)`
+        (_, Atom::List(TokenKind::Less)) => false,
+        (Atom::Token(TokenKind::Keyword(Keyword::Pub)), Atom::List(TokenKind::ParenOpen)) => false,
+        (Atom::Token(TokenKind::Keyword(_)), _) => true,
+        // `fn (A) (B)`
+        (Atom::List(TokenKind::ParenOpen), Atom::List(TokenKind::ParenOpen)) => {
+            parent == NodeKind::FnTy
+        }
+        (_, Atom::List(TokenKind::BraceOpen)) => true,
+        (_, Atom::List(_)) => false,
+        (_, Atom::Node(node)) if node.kind() == NodeKind::GenericArgs => false,
+        _ => true,
+    }
+}
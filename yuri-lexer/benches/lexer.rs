@@ -0,0 +1,33 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use yuri_lexer::{next_token, FileId, Input, Lexer, TokenKind};
+
+/// The corpus repeated to a few megabytes.
+fn source() -> String {
+    let corpus = include_str!("corpus.yuri");
+    corpus.repeat(4 * 1024 * 1024 / corpus.len() + 1)
+}
+
+fn nom_count(src: &str) -> usize {
+    let mut rest = Input::new_extra(src, FileId::default());
+    let mut count = 0;
+    while let Ok((next, token)) = next_token(rest) {
+        debug_assert_ne!(token.kind, TokenKind::Error);
+        rest = next;
+        count += 1;
+    }
+    assert!(rest.fragment().is_empty());
+    count
+}
+
+fn lexer(c: &mut Criterion) {
+    let src = source();
+    let mut group = c.benchmark_group("lexer");
+    group.throughput(Throughput::Bytes(src.len() as u64));
+    group.sample_size(20);
+    group.bench_function("hand-written", |b| b.iter(|| Lexer::new(&src).count()));
+    group.bench_function("nom", |b| b.iter(|| nom_count(&src)));
+    group.finish();
+}
+
+criterion_group!(benches, lexer);
+criterion_main!(benches);
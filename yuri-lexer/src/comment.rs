@@ -0,0 +1,129 @@
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_till};
+use nom::character::complete::multispace1;
+use nom::combinator::{not, recognize};
+use nom::multi::many0;
+use nom::sequence::{pair, preceded};
+use nom::{IResult, Slice};
+
+use crate::{Input, LexError, LexErrorKind, Token, TokenKind};
+
+/// Whether a doc comment documents the following item (`///`) or the
+/// enclosing one (`//!`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DocStyle {
+    Outer,
+    Inner,
+}
+
+fn is_newline(c: char) -> bool {
+    c == '\n'
+}
+
+/// `///` but not `////`, which is an ordinary comment.
+fn outer_doc_start(s: Input) -> IResult<Input, Input, LexError> {
+    recognize(pair(tag("///"), not(tag("/"))))(s)
+}
+
+/// `// ...` up to (but excluding) the newline. Doc comments are not matched.
+pub(crate) fn line_comment(s: Input) -> IResult<Input, Input, LexError> {
+    not(outer_doc_start)(s)?;
+    not(tag("//!"))(s)?;
+    recognize(preceded(tag("//"), take_till(is_newline)))(s)
+}
+
+/// `/* ... */`, which may nest. An unterminated comment is reported at its
+/// opening `/*`.
+pub(crate) fn block_comment(s: Input) -> IResult<Input, Input, LexError> {
+    tag("/*")(s)?;
+    let bytes = s.fragment().as_bytes();
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Ok((s.slice(i..), s.slice(..i)));
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    Err(nom::Err::Failure(LexError::at(
+        LexErrorKind::UnterminatedBlockComment,
+        s.slice(..2),
+    )))
+}
+
+/// Skips whitespace and non-doc comments.
+pub(crate) fn skip_trivia(s: Input) -> IResult<Input, (), LexError> {
+    let (s, _) = many0(alt((multispace1, line_comment, block_comment)))(s)?;
+    Ok((s, ()))
+}
+
+/// `/// ...` and `//! ...`.
+pub(crate) fn token_doc_comment(s: Input) -> IResult<Input, Token, LexError> {
+    let (_, style) = alt((
+        |s| outer_doc_start(s).map(|(s, _)| (s, DocStyle::Outer)),
+        |s| tag("//!")(s).map(|(s, _)| (s, DocStyle::Inner)),
+    ))(s)?;
+    let (rest, span) = take_till(is_newline)(s)?;
+    Ok((rest, Token::new(TokenKind::DocComment(style), span)))
+}
+
+impl<'a> Token<'a> {
+    /// The text of a doc comment token without its `///` or `//!` marker.
+    pub fn doc_text(&self) -> Option<&'a str> {
+        match self.kind {
+            TokenKind::DocComment(_) => Some(&self.text[3..]),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tokenize, Lexer};
+
+    fn kinds(src: &str) -> Vec<TokenKind> {
+        tokenize(src).iter().map(|t| t.kind).collect()
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("a // b / c\n/ d /* e /* f */ g */ h //"),
+            vec![Ident, Slash, Ident, Ident, Eof]
+        );
+        assert_eq!(kinds("//// not a doc comment\n/**/"), vec![Eof]);
+    }
+
+    #[test]
+    fn doc_comments() {
+        let tokens = tokenize("//! crate docs\n/// item docs\nfn");
+        assert_eq!(tokens[0].kind, TokenKind::DocComment(DocStyle::Inner));
+        assert_eq!(tokens[0].doc_text(), Some(" crate docs"));
+        assert_eq!(tokens[1].kind, TokenKind::DocComment(DocStyle::Outer));
+        assert_eq!(tokens[1].doc_text(), Some(" item docs"));
+        assert_eq!(tokens[2].kind, TokenKind::Keyword(crate::Keyword::Fn));
+    }
+
+    #[test]
+    fn unterminated_block_comment() {
+        let src = "a\n  /* b /* c */";
+        let mut lexer = Lexer::new(src);
+        assert_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::Ident);
+        let err = lexer.next().unwrap().unwrap_err();
+        assert_eq!(err.kind, LexErrorKind::UnterminatedBlockComment);
+        assert_eq!(&src[err.span.range()], "/*");
+        assert_eq!(err.span.lo, 4);
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+
+use nom::error::{ErrorKind, ParseError};
+
+use crate::{Base, Input, Span};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LexErrorKind {
+    /// No token starts with this character.
+    UnexpectedChar,
+    /// An integer literal has a prefix but no digits, e.g. `0x`.
+    EmptyInt,
+    /// A digit is out of range for the literal's base, e.g. `0b2`.
+    InvalidDigit(Base),
+    /// A literal is followed by an unknown suffix, e.g. `1abc`.
+    InvalidSuffix,
+    /// An integer, duration or size literal does not fit in its type.
+    IntOverflow,
+    /// A string or character literal is missing its closing quote.
+    UnterminatedLiteral,
+    /// An unknown or malformed escape sequence.
+    InvalidEscape,
+    /// An escape whose value is out of range, e.g. `"\x80"` or `'\u{110000}'`.
+    OutOfRangeEscape,
+    /// A non-ASCII character inside a byte or byte string literal.
+    NonAsciiByte,
+    /// `''`.
+    EmptyChar,
+    /// A character literal with more than one character, e.g. `'ab'`.
+    OverlongChar,
+    /// A character that must be escaped inside a character literal.
+    UnescapedChar,
+    /// A raw string delimited by more than 255 `#`s.
+    TooManyHashes,
+    /// A `/*` without its matching `*/`.
+    UnterminatedBlockComment,
+    /// A reserved word used as an identifier.
+    ReservedWord,
+    /// A keyword that cannot be a raw identifier, e.g. `r#self`.
+    InvalidRawIdent,
+    /// A nom combinator failed without a more specific reason.
+    Nom(ErrorKind),
+}
+
+/// An error produced while lexing. `span` points at the offending source text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LexError {
+    pub kind: LexErrorKind,
+    pub span: Span,
+}
+
+impl LexError {
+    pub fn new(kind: LexErrorKind, span: Span) -> Self {
+        LexError { kind, span }
+    }
+
+    pub(crate) fn at(kind: LexErrorKind, input: Input) -> Self {
+        LexError::new(kind, Span::of(&input))
+    }
+}
+
+impl<'a> ParseError<Input<'a>> for LexError {
+    fn from_error_kind(input: Input<'a>, kind: ErrorKind) -> Self {
+        LexError::at(LexErrorKind::Nom(kind), input)
+    }
+
+    fn append(_input: Input<'a>, _kind: ErrorKind, other: Self) -> Self {
+        other
+    }
+}
+
+impl fmt::Display for LexErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LexErrorKind::UnexpectedChar => write!(f, "unexpected character"),
+            LexErrorKind::EmptyInt => write!(f, "no valid digits found for number"),
+            LexErrorKind::InvalidDigit(base) => {
+                write!(f, "invalid digit for a base {} literal", base.radix())
+            }
+            LexErrorKind::InvalidSuffix => write!(f, "invalid suffix for literal"),
+            LexErrorKind::IntOverflow => write!(f, "integer literal is too large"),
+            LexErrorKind::UnterminatedLiteral => write!(f, "unterminated literal"),
+            LexErrorKind::InvalidEscape => write!(f, "invalid escape sequence"),
+            LexErrorKind::OutOfRangeEscape => write!(f, "escape sequence out of range"),
+            LexErrorKind::NonAsciiByte => write!(f, "non-ASCII character in byte literal"),
+            LexErrorKind::EmptyChar => write!(f, "empty character literal"),
+            LexErrorKind::OverlongChar => {
+                write!(f, "character literal may only contain one codepoint")
+            }
+            LexErrorKind::UnescapedChar => {
+                write!(f, "character constant must be escaped")
+            }
+            LexErrorKind::TooManyHashes => {
+                write!(
+                    f,
+                    "too many `#` symbols: raw strings may be delimited by up to 255"
+                )
+            }
+            LexErrorKind::UnterminatedBlockComment => write!(f, "unterminated block comment"),
+            LexErrorKind::ReservedWord => {
+                write!(f, "reserved word cannot be used as an identifier")
+            }
+            LexErrorKind::InvalidRawIdent => write!(f, "keyword cannot be a raw identifier"),
+            LexErrorKind::Nom(kind) => write!(f, "{}", kind.description()),
+        }
+    }
+}
+
+impl fmt::Display for LexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+impl std::error::Error for LexError {}
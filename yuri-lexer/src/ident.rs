@@ -0,0 +1,274 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+use nom::bytes::complete::{tag, take_while};
+use nom::character::complete::anychar;
+use nom::combinator::{recognize, verify};
+use nom::sequence::{pair, preceded};
+use nom::{IResult, Slice};
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_security::{skeleton, MixedScript};
+use unicode_xid::UnicodeXID;
+
+use crate::{Input, Keyword, LexError, LexErrorKind, Span, Token, TokenKind, RESERVED};
+
+pub(crate) fn is_ident_start(c: char) -> bool {
+    c == '_' || c.is_xid_start()
+}
+
+pub(crate) fn is_ident_continue(c: char) -> bool {
+    c.is_xid_continue()
+}
+
+/// `XID_Start XID_Continue*` as in UAX #31, additionally allowing a leading `_`.
+fn ident_body(s: Input) -> IResult<Input, Input, LexError> {
+    recognize(pair(
+        verify(anychar, |&c| is_ident_start(c)),
+        take_while(is_ident_continue),
+    ))(s)
+}
+
+fn failure<T>(kind: LexErrorKind, span: Input) -> IResult<Input, T, LexError> {
+    Err(nom::Err::Failure(LexError::at(kind, span)))
+}
+
+/// Identifiers, keywords and `_`.
+pub(crate) fn token_ident(s: Input) -> IResult<Input, Token, LexError> {
+    let (rest, span) = ident_body(s)?;
+    let kind = if *span.fragment() == "_" {
+        TokenKind::Underscore
+    } else if let Some(keyword) = Keyword::lookup(span.fragment()) {
+        TokenKind::Keyword(keyword)
+    } else if RESERVED.contains(span.fragment()) {
+        return failure(LexErrorKind::ReservedWord, span);
+    } else {
+        TokenKind::Ident
+    };
+    Ok((rest, Token::new(kind, span)))
+}
+
+/// Raw identifiers such as `r#match`, which are never keywords.
+pub(crate) fn token_raw_ident(s: Input) -> IResult<Input, Token, LexError> {
+    let (rest, name) = preceded(tag("r#"), ident_body)(s)?;
+    let span = s.slice(..2 + name.fragment().len());
+    let invalid = *name.fragment() == "_"
+        || Keyword::lookup(name.fragment()).is_some_and(Keyword::is_path_segment);
+    if invalid {
+        return failure(LexErrorKind::InvalidRawIdent, span);
+    }
+    Ok((rest, Token::new(TokenKind::Ident, span)))
+}
+
+/// Lifetime labels such as `'a`. An identifier followed by another `'` is a
+/// character literal instead.
+pub(crate) fn token_lifetime(s: Input) -> IResult<Input, Token, LexError> {
+    let (rest, name) = preceded(tag("'"), ident_body)(s)?;
+    if rest.fragment().starts_with('\'') {
+        return Err(nom::Err::Error(LexError::at(
+            LexErrorKind::Nom(nom::error::ErrorKind::Tag),
+            s,
+        )));
+    }
+    let span = s.slice(..1 + name.fragment().len());
+    Ok((rest, Token::new(TokenKind::Lifetime, span)))
+}
+
+/// Normalizes an identifier to NFC so that canonically equivalent spellings
+/// compare equal. Borrows the input when it is already normalized.
+pub fn normalize_ident(name: &str) -> Cow<'_, str> {
+    match is_nfc_quick(name.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(name),
+        _ => Cow::Owned(name.nfc().collect()),
+    }
+}
+
+impl<'a> Token<'a> {
+    /// The name of an identifier token, without the `r#` of raw identifiers.
+    pub fn ident_name(&self) -> Option<&'a str> {
+        match self.kind {
+            TokenKind::Ident => Some(self.text.strip_prefix("r#").unwrap_or(self.text)),
+            _ => None,
+        }
+    }
+
+    /// The NFC-normalized name of an identifier token. Use this rather than
+    /// `ident_name` when comparing identifiers.
+    pub fn normalized_name(&self) -> Option<Cow<'a, str>> {
+        self.ident_name().map(normalize_ident)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdentLintKind {
+    /// The identifier mixes characters from several scripts, e.g. Latin and
+    /// Cyrillic, which is how lookalike identifiers are usually built.
+    MixedScript,
+    /// The identifier looks like a different identifier used earlier.
+    Confusable,
+}
+
+/// A warning about an identifier that is valid but potentially misleading.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IdentLint {
+    pub kind: IdentLintKind,
+    pub span: Span,
+    /// For `Confusable`, the first occurrence of the identifier it resembles.
+    pub other: Option<Span>,
+}
+
+impl fmt::Display for IdentLintKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IdentLintKind::MixedScript => write!(f, "identifier mixes multiple scripts"),
+            IdentLintKind::Confusable => {
+                write!(f, "identifier is confusable with another identifier")
+            }
+        }
+    }
+}
+
+/// Checks the identifiers in `tokens` for mixed-script and confusable
+/// spellings following UTS #39. Purely ASCII identifiers are never reported as
+/// confusable with each other.
+pub fn lint_idents<'a>(tokens: &[Token<'a>]) -> Vec<IdentLint> {
+    let mut lints = Vec::new();
+    // skeleton -> (normalized name, first span)
+    let mut seen: HashMap<String, (Cow<'a, str>, Span)> = HashMap::new();
+    for token in tokens {
+        let name = match token.normalized_name() {
+            Some(name) => name,
+            None => continue,
+        };
+        if !name.is_single_script() {
+            lints.push(IdentLint {
+                kind: IdentLintKind::MixedScript,
+                span: token.span,
+                other: None,
+            });
+        }
+        let key: String = skeleton(&name).collect();
+        match seen.get(&key) {
+            Some((first, span)) => {
+                if *first != name && !(first.is_ascii() && name.is_ascii()) {
+                    lints.push(IdentLint {
+                        kind: IdentLintKind::Confusable,
+                        span: token.span,
+                        other: Some(*span),
+                    });
+                }
+            }
+            None => {
+                seen.insert(key, (name, token.span));
+            }
+        }
+    }
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tokenize, Lexer};
+
+    #[test]
+    fn unicode_and_underscores() {
+        let tokens = tokenize("バッファ _tmp read__stack x_1 ñandú _ __");
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Ident,
+                TokenKind::Ident,
+                TokenKind::Ident,
+                TokenKind::Ident,
+                TokenKind::Ident,
+                TokenKind::Underscore,
+                TokenKind::Ident,
+                TokenKind::Eof,
+            ]
+        );
+        assert_eq!(tokens[0].ident_name(), Some("バッファ"));
+        assert_eq!(tokens[2].ident_name(), Some("read__stack"));
+    }
+
+    #[test]
+    fn reserved_words() {
+        let src = "let async = 1";
+        let err = Lexer::new(src).find_map(Result::err).unwrap();
+        assert_eq!(err.kind, LexErrorKind::ReservedWord);
+        assert_eq!(&src[err.span.range()], "async");
+    }
+
+    #[test]
+    fn raw_identifiers() {
+        let tokens = tokenize("r#match r#async r#fn");
+        assert!(tokens[..3].iter().all(|t| t.kind == TokenKind::Ident));
+        assert_eq!(tokens[0].text, "r#match");
+        assert_eq!(tokens[0].ident_name(), Some("match"));
+        assert_eq!(tokens[1].ident_name(), Some("async"));
+
+        for src in &["r#self", "r#_"] {
+            let err = Lexer::new(src).find_map(Result::err).unwrap();
+            assert_eq!(err.kind, LexErrorKind::InvalidRawIdent);
+            assert_eq!(err.span.range(), 0..src.len());
+        }
+    }
+
+    #[test]
+    fn lifetimes() {
+        let tokens = tokenize("&'a mut 'static 'x' '_ 'ユ");
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Amp,
+                TokenKind::Lifetime,
+                TokenKind::Keyword(Keyword::Mut),
+                TokenKind::Lifetime,
+                TokenKind::Char,
+                TokenKind::Lifetime,
+                TokenKind::Lifetime,
+                TokenKind::Eof,
+            ]
+        );
+        assert_eq!(tokens[1].text, "'a");
+        assert_eq!(tokens[3].text, "'static");
+    }
+
+    #[test]
+    fn nfc_equality() {
+        // "é" precomposed and as "e" + combining acute accent.
+        let tokens = tokenize("caf\u{e9} cafe\u{301}");
+        assert_ne!(tokens[0].ident_name(), tokens[1].ident_name());
+        assert_eq!(tokens[0].normalized_name(), tokens[1].normalized_name());
+        assert!(matches!(
+            tokens[0].normalized_name(),
+            Some(Cow::Borrowed(_))
+        ));
+    }
+
+    #[test]
+    fn lints() {
+        // The second `paypal` starts with a Cyrillic `р`.
+        let src = "paypal \u{440}aypal バッファ burn bum";
+        let lints = lint_idents(&tokenize(src));
+        let lints: Vec<_> = lints
+            .iter()
+            .map(|l| {
+                (
+                    l.kind,
+                    &src[l.span.range()],
+                    l.other.map(|s| &src[s.range()]),
+                )
+            })
+            .collect();
+        assert_eq!(
+            lints,
+            vec![
+                (IdentLintKind::MixedScript, "\u{440}aypal", None),
+                (IdentLintKind::Confusable, "\u{440}aypal", Some("paypal")),
+            ]
+        );
+    }
+}
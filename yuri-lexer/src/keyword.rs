@@ -0,0 +1,107 @@
+macro_rules! keywords {
+    ($($name:ident => $s:expr,)*) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum Keyword {
+            $($name,)*
+        }
+
+        impl Keyword {
+            pub const ALL: &'static [Keyword] = &[$(Keyword::$name,)*];
+
+            pub fn lookup(s: &str) -> Option<Keyword> {
+                match s {
+                    $($s => Some(Keyword::$name),)*
+                    _ => None,
+                }
+            }
+
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(Keyword::$name => $s,)*
+                }
+            }
+        }
+    };
+}
+
+keywords! {
+    As => "as",
+    Break => "break",
+    Const => "const",
+    Continue => "continue",
+    Crate => "crate",
+    Else => "else",
+    Enum => "enum",
+    False => "false",
+    Fn => "fn",
+    For => "for",
+    If => "if",
+    Impl => "impl",
+    In => "in",
+    Let => "let",
+    Loop => "loop",
+    Match => "match",
+    Mod => "mod",
+    Mut => "mut",
+    Pub => "pub",
+    Ref => "ref",
+    Return => "return",
+    SelfValue => "self",
+    SelfType => "Self",
+    Static => "static",
+    Struct => "struct",
+    Super => "super",
+    Trait => "trait",
+    True => "true",
+    Type => "type",
+    Use => "use",
+    Where => "where",
+    While => "while",
+    Yield => "yield",
+}
+
+/// Words that are not keywords yet but may become ones, so they cannot be
+/// used as plain identifiers. `async` and `await` are reserved because every
+/// function is implicitly asynchronous.
+pub const RESERVED: &[&str] = &[
+    "abstract", "async", "await", "box", "do", "dyn", "final", "macro", "move", "override", "priv",
+    "typeof", "unsafe", "unsized", "virtual",
+];
+
+impl Keyword {
+    /// Path keywords cannot be escaped with `r#`.
+    pub(crate) fn is_path_segment(self) -> bool {
+        matches!(
+            self,
+            Keyword::SelfValue | Keyword::SelfType | Keyword::Super | Keyword::Crate
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tokenize, TokenKind};
+
+    #[test]
+    fn keywords() {
+        for &keyword in Keyword::ALL {
+            let tokens = tokenize(keyword.as_str());
+            assert_eq!(tokens[0].kind, TokenKind::Keyword(keyword));
+        }
+        let kinds: Vec<_> = tokenize("fn copy loop letter")
+            .iter()
+            .map(|t| t.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Keyword(Keyword::Fn),
+                TokenKind::Ident,
+                TokenKind::Keyword(Keyword::Loop),
+                TokenKind::Ident,
+                TokenKind::Eof,
+            ]
+        );
+    }
+}
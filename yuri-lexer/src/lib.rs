@@ -1,40 +1,255 @@
 use nom::branch::alt;
-use nom::character::complete::{alpha1, alphanumeric0, multispace0};
-use nom::combinator::recognize;
-use nom::sequence::{delimited, tuple};
+use nom::sequence::delimited;
 use nom::IResult;
 
-type Span<'a> = nom_locate::LocatedSpan<&'a str>;
+mod comment;
+mod error;
+mod ident;
+mod keyword;
+mod literal;
+mod recovery;
+mod relex;
+mod scan;
+mod source_map;
+mod stream;
+mod string;
+mod symbol;
 
+use comment::skip_trivia;
+
+pub use comment::DocStyle;
+pub use error::{LexError, LexErrorKind};
+pub use ident::{lint_idents, normalize_ident, IdentLint, IdentLintKind};
+pub use keyword::{Keyword, RESERVED};
+pub use literal::{Base, FloatTy, IntTy, SizeUnit, TimeUnit};
+pub use relex::TextEdit;
+pub use source_map::{FileId, LineCol, SourceFile, SourceMap, Span};
+pub use stream::{tokenize, tokenize_lossless, tokenize_with_errors, Lexer};
+pub use string::{unescape_byte_str, unescape_str};
+pub use symbol::{Intern, Interner, Symbol, SyncInterner};
+
+/// The input of the nom lexer: a fragment of a source file that knows its
+/// offset and file.
+pub type Input<'a> = nom_locate::LocatedSpan<&'a str, FileId>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum TokenKind {
+    /// Identifier, including raw identifiers such as `r#match`.
     Ident,
+    Keyword(Keyword),
+    /// `_` on its own.
+    Underscore,
+    /// `/// ...` or `//! ...`.
+    DocComment(DocStyle),
+    /// Whitespace. Only produced in lossless mode.
+    Whitespace,
+    /// `// ...` without the trailing newline. Only produced in lossless mode.
+    LineComment,
+    /// `/* ... */`. Only produced in lossless mode.
+    BlockComment,
+    /// Text skipped after a lexical error.
+    Error,
+    /// Integer literal such as `1024`, `0xff` or `0u8`.
+    Int {
+        base: Base,
+        suffix: Option<IntTy>,
+    },
+    /// Float literal such as `1.5`, `1e-9` or `2.0f32`.
+    Float {
+        suffix: Option<FloatTy>,
+    },
+    /// Duration literal such as `3s` or `250ms`.
+    Duration(TimeUnit),
+    /// Byte-size literal such as `4KiB`.
+    Size(SizeUnit),
+    /// String literal `"..."`.
+    Str,
+    /// Byte string literal `b"..."`.
+    ByteStr,
+    /// Raw string literal `r#"..."#` delimited by `hashes` `#`s.
+    RawStr {
+        hashes: u8,
+    },
+    /// Raw byte string literal `br#"..."#` delimited by `hashes` `#`s.
+    RawByteStr {
+        hashes: u8,
+    },
+    /// Character literal `'c'`.
+    Char,
+    /// Byte literal `b'c'`.
+    Byte,
+    /// Lifetime label such as `'a`, which unlike `'a'` has no closing `'`.
+    Lifetime,
     ParenOpen,
     ParenClose,
     BraceOpen,
     BraceClose,
+    BracketOpen,
+    BracketClose,
     Colon,
+    ColonColon,
+    Semicolon,
+    Comma,
+    Dot,
+    DotDot,
+    DotDotEqual,
+    /// `->`
+    Arrow,
+    /// `=>`
+    FatArrow,
+    Question,
+    Pound,
+    At,
     Equal,
+    EqualEqual,
+    Bang,
+    BangEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
     Plus,
+    PlusEqual,
     Minus,
+    MinusEqual,
     Star,
+    StarEqual,
     Slash,
+    SlashEqual,
+    Percent,
+    PercentEqual,
+    Caret,
+    CaretEqual,
+    Amp,
+    AmpAmp,
+    AmpEqual,
+    Pipe,
+    PipePipe,
+    PipeEqual,
+    /// `<<`
+    LessLess,
+    /// `<<=`
+    LessLessEqual,
+    /// `>>`
+    GreaterGreater,
+    /// `>>=`
+    GreaterGreaterEqual,
+    /// End of input. Emitted exactly once by `Lexer` after the last token.
+    Eof,
+}
+
+impl TokenKind {
+    /// The variant name without its payload, such as `"Int"` for any
+    /// integer literal. Stable for tools that consume token dumps.
+    pub fn name(self) -> &'static str {
+        use TokenKind::*;
+        match self {
+            Ident => "Ident",
+            Keyword(_) => "Keyword",
+            Underscore => "Underscore",
+            DocComment(_) => "DocComment",
+            Whitespace => "Whitespace",
+            LineComment => "LineComment",
+            BlockComment => "BlockComment",
+            Error => "Error",
+            Int { .. } => "Int",
+            Float { .. } => "Float",
+            Duration(_) => "Duration",
+            Size(_) => "Size",
+            Str => "Str",
+            ByteStr => "ByteStr",
+            RawStr { .. } => "RawStr",
+            RawByteStr { .. } => "RawByteStr",
+            Char => "Char",
+            Byte => "Byte",
+            Lifetime => "Lifetime",
+            ParenOpen => "ParenOpen",
+            ParenClose => "ParenClose",
+            BraceOpen => "BraceOpen",
+            BraceClose => "BraceClose",
+            BracketOpen => "BracketOpen",
+            BracketClose => "BracketClose",
+            Colon => "Colon",
+            ColonColon => "ColonColon",
+            Semicolon => "Semicolon",
+            Comma => "Comma",
+            Dot => "Dot",
+            DotDot => "DotDot",
+            DotDotEqual => "DotDotEqual",
+            Arrow => "Arrow",
+            FatArrow => "FatArrow",
+            Question => "Question",
+            Pound => "Pound",
+            At => "At",
+            Equal => "Equal",
+            EqualEqual => "EqualEqual",
+            Bang => "Bang",
+            BangEqual => "BangEqual",
+            Less => "Less",
+            LessEqual => "LessEqual",
+            Greater => "Greater",
+            GreaterEqual => "GreaterEqual",
+            Plus => "Plus",
+            PlusEqual => "PlusEqual",
+            Minus => "Minus",
+            MinusEqual => "MinusEqual",
+            Star => "Star",
+            StarEqual => "StarEqual",
+            Slash => "Slash",
+            SlashEqual => "SlashEqual",
+            Percent => "Percent",
+            PercentEqual => "PercentEqual",
+            Caret => "Caret",
+            CaretEqual => "CaretEqual",
+            Amp => "Amp",
+            AmpAmp => "AmpAmp",
+            AmpEqual => "AmpEqual",
+            Pipe => "Pipe",
+            PipePipe => "PipePipe",
+            PipeEqual => "PipeEqual",
+            LessLess => "LessLess",
+            LessLessEqual => "LessLessEqual",
+            GreaterGreater => "GreaterGreater",
+            GreaterGreaterEqual => "GreaterGreaterEqual",
+            Eof => "Eof",
+        }
+    }
+
+    /// Whitespace and ordinary comments, which the parser ignores.
+    pub fn is_trivia(self) -> bool {
+        matches!(
+            self,
+            TokenKind::Whitespace | TokenKind::LineComment | TokenKind::BlockComment
+        )
+    }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Token<'a> {
     pub kind: TokenKind,
-    pub span: Span<'a>,
+    pub span: Span,
+    /// The source text covered by `span`.
+    pub text: &'a str,
+    /// The interned name or literal text, for tokens of a kind that
+    /// `TokenKind::has_symbol` and only when lexed with an interner.
+    pub symbol: Option<Symbol>,
+}
+
+impl<'a> Token<'a> {
+    pub(crate) fn new(kind: TokenKind, input: Input<'a>) -> Self {
+        Token {
+            kind,
+            span: Span::of(&input),
+            text: input.fragment(),
+            symbol: None,
+        }
+    }
 }
 
 macro_rules! token_symbol {
     ($name:ident, $tag:expr, $kind:expr) => {
-        fn $name(s: Span) -> IResult<Span, Token> {
-            nom::bytes::complete::tag($tag)(s)
-                .map(|(s, span)| {
-                    (s, Token {
-                        kind: $kind,
-                        span,
-                    })
-                })
+        fn $name(s: Input) -> IResult<Input, Token, LexError> {
+            nom::bytes::complete::tag($tag)(s).map(|(s, span)| (s, Token::new($kind, span)))
         }
     };
 }
@@ -43,37 +258,242 @@ token_symbol!(token_paren_open, "(", TokenKind::ParenOpen);
 token_symbol!(token_paren_close, ")", TokenKind::ParenClose);
 token_symbol!(token_brace_open, "{", TokenKind::BraceOpen);
 token_symbol!(token_brace_close, "}", TokenKind::BraceClose);
+token_symbol!(token_bracket_open, "[", TokenKind::BracketOpen);
+token_symbol!(token_bracket_close, "]", TokenKind::BracketClose);
 token_symbol!(token_colon, ":", TokenKind::Colon);
+token_symbol!(token_colon_colon, "::", TokenKind::ColonColon);
+token_symbol!(token_semicolon, ";", TokenKind::Semicolon);
+token_symbol!(token_comma, ",", TokenKind::Comma);
+token_symbol!(token_dot, ".", TokenKind::Dot);
+token_symbol!(token_dot_dot, "..", TokenKind::DotDot);
+token_symbol!(token_dot_dot_equal, "..=", TokenKind::DotDotEqual);
+token_symbol!(token_arrow, "->", TokenKind::Arrow);
+token_symbol!(token_fat_arrow, "=>", TokenKind::FatArrow);
+token_symbol!(token_question, "?", TokenKind::Question);
+token_symbol!(token_pound, "#", TokenKind::Pound);
+token_symbol!(token_at, "@", TokenKind::At);
 token_symbol!(token_equal, "=", TokenKind::Equal);
+token_symbol!(token_equal_equal, "==", TokenKind::EqualEqual);
+token_symbol!(token_bang, "!", TokenKind::Bang);
+token_symbol!(token_bang_equal, "!=", TokenKind::BangEqual);
+token_symbol!(token_less, "<", TokenKind::Less);
+token_symbol!(token_less_equal, "<=", TokenKind::LessEqual);
+token_symbol!(token_greater, ">", TokenKind::Greater);
+token_symbol!(token_greater_equal, ">=", TokenKind::GreaterEqual);
 token_symbol!(token_plus, "+", TokenKind::Plus);
+token_symbol!(token_plus_equal, "+=", TokenKind::PlusEqual);
 token_symbol!(token_minus, "-", TokenKind::Minus);
+token_symbol!(token_minus_equal, "-=", TokenKind::MinusEqual);
 token_symbol!(token_star, "*", TokenKind::Star);
+token_symbol!(token_star_equal, "*=", TokenKind::StarEqual);
 token_symbol!(token_slash, "/", TokenKind::Slash);
+token_symbol!(token_slash_equal, "/=", TokenKind::SlashEqual);
+token_symbol!(token_percent, "%", TokenKind::Percent);
+token_symbol!(token_percent_equal, "%=", TokenKind::PercentEqual);
+token_symbol!(token_caret, "^", TokenKind::Caret);
+token_symbol!(token_caret_equal, "^=", TokenKind::CaretEqual);
+token_symbol!(token_amp, "&", TokenKind::Amp);
+token_symbol!(token_amp_amp, "&&", TokenKind::AmpAmp);
+token_symbol!(token_amp_equal, "&=", TokenKind::AmpEqual);
+token_symbol!(token_pipe, "|", TokenKind::Pipe);
+token_symbol!(token_pipe_pipe, "||", TokenKind::PipePipe);
+token_symbol!(token_pipe_equal, "|=", TokenKind::PipeEqual);
+token_symbol!(token_less_less, "<<", TokenKind::LessLess);
+token_symbol!(token_less_less_equal, "<<=", TokenKind::LessLessEqual);
+token_symbol!(token_greater_greater, ">>", TokenKind::GreaterGreater);
+token_symbol!(
+    token_greater_greater_equal,
+    ">>=",
+    TokenKind::GreaterGreaterEqual
+);
 
-fn token_ident(s: Span) -> IResult<Span, Token> {
-    // alphabetic followed by alphanumerics
-    recognize(tuple((alpha1, alphanumeric0)))(s)
-        .map(|(s, span)| {
-            (s, Token {
-                kind: TokenKind::Ident,
-                span,
-            })
-        })
-}
-
-pub fn next_token(s: Span) -> IResult<Span, Token> {
-    let alt = alt((
+// Longer symbols must be tried before their prefixes so that e.g. `==` is
+// never lexed as two `=`s.
+fn token_punct(s: Input) -> IResult<Input, Token, LexError> {
+    let three = alt((
+        token_dot_dot_equal,
+        token_less_less_equal,
+        token_greater_greater_equal,
+    ));
+    let two = alt((
+        token_colon_colon,
+        token_dot_dot,
+        token_arrow,
+        token_fat_arrow,
+        token_equal_equal,
+        token_bang_equal,
+        token_less_equal,
+        token_greater_equal,
+        token_plus_equal,
+        token_minus_equal,
+        token_star_equal,
+        token_slash_equal,
+        token_percent_equal,
+        token_caret_equal,
+        token_amp_amp,
+        token_amp_equal,
+        token_pipe_pipe,
+        token_pipe_equal,
+        token_less_less,
+        token_greater_greater,
+    ));
+    let delimiter = alt((
         token_paren_open,
         token_paren_close,
         token_brace_open,
         token_brace_close,
+        token_bracket_open,
+        token_bracket_close,
+    ));
+    let one = alt((
         token_colon,
+        token_semicolon,
+        token_comma,
+        token_dot,
+        token_question,
+        token_pound,
+        token_at,
         token_equal,
+        token_bang,
+        token_less,
+        token_greater,
         token_plus,
         token_minus,
         token_star,
         token_slash,
-        token_ident,
+        token_percent,
+        token_caret,
+        token_amp,
+        token_pipe,
     ));
-    delimited(multispace0, alt, multispace0)(s)
-}
\ No newline at end of file
+    alt((three, two, delimiter, one))(s)
+}
+
+/// A single token, without the trivia around it.
+pub(crate) fn token(s: Input) -> IResult<Input, Token, LexError> {
+    alt((
+        comment::token_doc_comment,
+        token_punct,
+        literal::token_int,
+        string::token_raw_str,
+        string::token_str,
+        ident::token_lifetime,
+        string::token_char,
+        ident::token_raw_ident,
+        ident::token_ident,
+    ))(s)
+}
+
+/// Lexes one token and the trivia around it with the nom parsers. `Lexer`
+/// uses a faster hand-written lexer that produces the same tokens.
+pub fn next_token(s: Input) -> IResult<Input, Token, LexError> {
+    delimited(skip_trivia, token, skip_trivia)(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(src: &str) -> Vec<TokenKind> {
+        tokenize(src).iter().map(|t| t.kind).collect()
+    }
+
+    #[test]
+    fn longest_match() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("== => = ..= .. . :: : -> - <<= << <= < >>= >> >= >"),
+            vec![
+                EqualEqual,
+                FatArrow,
+                Equal,
+                DotDotEqual,
+                DotDot,
+                Dot,
+                ColonColon,
+                Colon,
+                Arrow,
+                Minus,
+                LessLessEqual,
+                LessLess,
+                LessEqual,
+                Less,
+                GreaterGreaterEqual,
+                GreaterGreater,
+                GreaterEqual,
+                Greater,
+                Eof,
+            ]
+        );
+        assert_eq!(
+            kinds("&& &= & || |= | != ! += *= /= %= ^= ^ % ? # @ ; ,"),
+            vec![
+                AmpAmp,
+                AmpEqual,
+                Amp,
+                PipePipe,
+                PipeEqual,
+                Pipe,
+                BangEqual,
+                Bang,
+                PlusEqual,
+                StarEqual,
+                SlashEqual,
+                PercentEqual,
+                CaretEqual,
+                Caret,
+                Percent,
+                Question,
+                Pound,
+                At,
+                Semicolon,
+                Comma,
+                Eof,
+            ]
+        );
+    }
+
+    #[test]
+    fn design_snippets() {
+        use TokenKind::*;
+        let int = Int {
+            base: Base::Decimal,
+            suffix: None,
+        };
+        assert_eq!(
+            kinds("write(dest, &buf[0..n]);"),
+            vec![
+                Ident,
+                ParenOpen,
+                Ident,
+                Comma,
+                Amp,
+                Ident,
+                BracketOpen,
+                int,
+                DotDot,
+                Ident,
+                BracketClose,
+                ParenClose,
+                Semicolon,
+                Eof,
+            ]
+        );
+        assert_eq!(
+            kinds("Poll::Ready(n) => break n,"),
+            vec![
+                Ident,
+                ColonColon,
+                Ident,
+                ParenOpen,
+                Ident,
+                ParenClose,
+                FatArrow,
+                Keyword(crate::Keyword::Break),
+                Ident,
+                Comma,
+                Eof,
+            ]
+        );
+    }
+}
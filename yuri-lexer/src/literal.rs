@@ -0,0 +1,562 @@
+use std::time::Duration;
+
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_while};
+use nom::character::complete::digit1;
+use nom::combinator::{peek, value};
+use nom::{IResult, Slice};
+
+use crate::ident::is_ident_start;
+use crate::{Input, LexError, LexErrorKind, Token, TokenKind};
+
+/// The radix of an integer literal, as selected by its prefix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Base {
+    Binary,
+    Octal,
+    Decimal,
+    Hexadecimal,
+}
+
+impl Base {
+    pub fn radix(self) -> u32 {
+        match self {
+            Base::Binary => 2,
+            Base::Octal => 8,
+            Base::Decimal => 10,
+            Base::Hexadecimal => 16,
+        }
+    }
+
+    /// Length of the `0x`/`0o`/`0b` prefix in bytes.
+    pub fn prefix_len(self) -> usize {
+        match self {
+            Base::Decimal => 0,
+            _ => 2,
+        }
+    }
+}
+
+macro_rules! int_ty {
+    ($($name:ident => $s:expr, $max:expr;)*) => {
+        /// The type suffix of an integer literal, e.g. `u8` in `0u8`.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum IntTy {
+            $($name,)*
+        }
+
+        impl IntTy {
+            pub fn from_suffix(s: &str) -> Option<IntTy> {
+                match s {
+                    $($s => Some(IntTy::$name),)*
+                    _ => None,
+                }
+            }
+
+            pub fn as_str(self) -> &'static str {
+                match self {
+                    $(IntTy::$name => $s,)*
+                }
+            }
+
+            /// The largest literal value this type accepts. Signed types
+            /// accept their minimum's magnitude so that `-128i8` can be written.
+            pub fn max_literal(self) -> u128 {
+                match self {
+                    $(IntTy::$name => $max,)*
+                }
+            }
+        }
+    };
+}
+
+int_ty! {
+    I8 => "i8", i8::MAX as u128 + 1;
+    I16 => "i16", i16::MAX as u128 + 1;
+    I32 => "i32", i32::MAX as u128 + 1;
+    I64 => "i64", i64::MAX as u128 + 1;
+    I128 => "i128", i128::MAX as u128 + 1;
+    Isize => "isize", i64::MAX as u128 + 1;
+    U8 => "u8", u8::MAX as u128;
+    U16 => "u16", u16::MAX as u128;
+    U32 => "u32", u32::MAX as u128;
+    U64 => "u64", u64::MAX as u128;
+    U128 => "u128", u128::MAX;
+    Usize => "usize", u64::MAX as u128;
+}
+
+/// The type suffix of a float literal, e.g. `f32` in `2.0f32`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum FloatTy {
+    F32,
+    F64,
+}
+
+impl FloatTy {
+    pub fn from_suffix(s: &str) -> Option<FloatTy> {
+        match s {
+            "f32" => Some(FloatTy::F32),
+            "f64" => Some(FloatTy::F64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FloatTy::F32 => "f32",
+            FloatTy::F64 => "f64",
+        }
+    }
+}
+
+/// The unit of a duration literal, e.g. `ms` in `250ms`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TimeUnit {
+    Nanos,
+    Micros,
+    Millis,
+    Secs,
+}
+
+impl TimeUnit {
+    pub fn from_suffix(s: &str) -> Option<TimeUnit> {
+        match s {
+            "ns" => Some(TimeUnit::Nanos),
+            "us" => Some(TimeUnit::Micros),
+            "ms" => Some(TimeUnit::Millis),
+            "s" => Some(TimeUnit::Secs),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimeUnit::Nanos => "ns",
+            TimeUnit::Micros => "us",
+            TimeUnit::Millis => "ms",
+            TimeUnit::Secs => "s",
+        }
+    }
+
+    pub fn nanos(self) -> u64 {
+        match self {
+            TimeUnit::Nanos => 1,
+            TimeUnit::Micros => 1_000,
+            TimeUnit::Millis => 1_000_000,
+            TimeUnit::Secs => 1_000_000_000,
+        }
+    }
+}
+
+/// The unit of a byte-size literal, e.g. `KiB` in `4KiB`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SizeUnit {
+    KiB,
+    MiB,
+    GiB,
+    TiB,
+}
+
+impl SizeUnit {
+    pub fn from_suffix(s: &str) -> Option<SizeUnit> {
+        match s {
+            "KiB" => Some(SizeUnit::KiB),
+            "MiB" => Some(SizeUnit::MiB),
+            "GiB" => Some(SizeUnit::GiB),
+            "TiB" => Some(SizeUnit::TiB),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SizeUnit::KiB => "KiB",
+            SizeUnit::MiB => "MiB",
+            SizeUnit::GiB => "GiB",
+            SizeUnit::TiB => "TiB",
+        }
+    }
+
+    pub fn bytes(self) -> u64 {
+        match self {
+            SizeUnit::KiB => 1 << 10,
+            SizeUnit::MiB => 1 << 20,
+            SizeUnit::GiB => 1 << 30,
+            SizeUnit::TiB => 1 << 40,
+        }
+    }
+}
+
+/// Parses the digits of an integer literal, skipping `_` separators.
+/// Returns `None` on overflow or an invalid digit.
+pub(crate) fn parse_digits(digits: &str, base: Base) -> Option<u128> {
+    digits
+        .chars()
+        .filter(|&c| c != '_')
+        .try_fold(0u128, |acc, c| {
+            let d = c.to_digit(base.radix())?;
+            acc.checked_mul(u128::from(base.radix()))?
+                .checked_add(u128::from(d))
+        })
+}
+
+/// Length of the fraction and exponent of a float literal at the start of
+/// `text`, which follows the integer part. Zero if there are none.
+///
+/// The fraction may be empty, as in `2.`, but only where the `.` is not
+/// followed by another `.`, an identifier or a digit, so that `0..n` stays a
+/// range and `1.foo()` a method call. `x.0.1` lexes `0.1` as a float, like
+/// Rust; the parser splits it when it expects a field.
+pub(crate) fn float_tail(text: &str) -> usize {
+    fn digits(bytes: &[u8]) -> usize {
+        bytes
+            .iter()
+            .take_while(|b| b.is_ascii_digit() || **b == b'_')
+            .count()
+    }
+    let bytes = text.as_bytes();
+    let mut len = 0;
+    if bytes.first() == Some(&b'.') {
+        match text[1..].chars().next() {
+            Some(c) if c.is_ascii_digit() => len = 1 + digits(&bytes[1..]),
+            Some(c) if c == '.' || c.is_alphanumeric() || is_ident_start(c) => {}
+            _ => return 1,
+        }
+    }
+    if let Some(b'e') | Some(b'E') = bytes.get(len) {
+        let sign = matches!(bytes.get(len + 1), Some(b'+') | Some(b'-')) as usize;
+        let exponent = &bytes[len + 1 + sign..];
+        let n = digits(exponent);
+        if exponent[..n].iter().any(u8::is_ascii_digit) {
+            len += 1 + sign + n;
+        }
+    }
+    len
+}
+
+/// The kind of a numeric literal with the given digits and suffix, where
+/// `float` tells whether it has a fraction or exponent. Durations must fit in
+/// `u64` nanoseconds and sizes in `u64` bytes.
+pub(crate) fn number_kind(
+    base: Base,
+    digits: &str,
+    float: bool,
+    suffix: &str,
+) -> Result<TokenKind, LexErrorKind> {
+    let float_ty = FloatTy::from_suffix(suffix).filter(|_| base == Base::Decimal);
+    if float || float_ty.is_some() {
+        return match float_ty {
+            Some(ty) => Ok(TokenKind::Float { suffix: Some(ty) }),
+            None if suffix.is_empty() => Ok(TokenKind::Float { suffix: None }),
+            None => Err(LexErrorKind::InvalidSuffix),
+        };
+    }
+    let value = parse_digits(digits, base);
+    let (kind, max) = if suffix.is_empty() {
+        (TokenKind::Int { base, suffix: None }, u128::MAX)
+    } else if let Some(ty) = IntTy::from_suffix(suffix) {
+        (
+            TokenKind::Int {
+                base,
+                suffix: Some(ty),
+            },
+            ty.max_literal(),
+        )
+    } else if let (Base::Decimal, Some(unit)) = (base, TimeUnit::from_suffix(suffix)) {
+        (
+            TokenKind::Duration(unit),
+            u128::from(u64::MAX / unit.nanos()),
+        )
+    } else if let (Base::Decimal, Some(unit)) = (base, SizeUnit::from_suffix(suffix)) {
+        (TokenKind::Size(unit), u128::from(u64::MAX / unit.bytes()))
+    } else {
+        return Err(LexErrorKind::InvalidSuffix);
+    };
+    if value.is_some_and(|n| n <= max) {
+        Ok(kind)
+    } else {
+        Err(LexErrorKind::IntOverflow)
+    }
+}
+
+fn int_base(s: Input) -> IResult<Input, Base, LexError> {
+    alt((
+        value(Base::Hexadecimal, tag("0x")),
+        value(Base::Octal, tag("0o")),
+        value(Base::Binary, tag("0b")),
+        value(Base::Decimal, peek(digit1)),
+    ))(s)
+}
+
+fn failure<T>(kind: LexErrorKind, span: Input) -> IResult<Input, T, LexError> {
+    Err(nom::Err::Failure(LexError::at(kind, span)))
+}
+
+pub(crate) fn token_int(s: Input) -> IResult<Input, Token, LexError> {
+    let (s1, base) = int_base(s)?;
+    let (s2, digits) = if base == Base::Hexadecimal {
+        take_while(|c: char| c.is_ascii_hexdigit() || c == '_')(s1)?
+    } else {
+        take_while(|c: char| c.is_ascii_digit() || c == '_')(s1)?
+    };
+    let tail = if base == Base::Decimal {
+        float_tail(s2.fragment())
+    } else {
+        0
+    };
+    let (rest, suffix) = take_while(|c: char| c.is_alphanumeric() || c == '_')(s2.slice(tail..))?;
+    let span = s.slice(..rest.location_offset() - s.location_offset());
+
+    if digits.fragment().chars().all(|c| c == '_') {
+        return failure(LexErrorKind::EmptyInt, span);
+    }
+    if let Some(i) = digits
+        .fragment()
+        .find(|c: char| c != '_' && c.to_digit(base.radix()).is_none())
+    {
+        return failure(LexErrorKind::InvalidDigit(base), digits.slice(i..i + 1));
+    }
+    let kind = match number_kind(base, digits.fragment(), tail > 0, suffix.fragment()) {
+        Ok(kind) => kind,
+        Err(LexErrorKind::InvalidSuffix) => return failure(LexErrorKind::InvalidSuffix, suffix),
+        Err(kind) => return failure(kind, span),
+    };
+    Ok((rest, Token::new(kind, span)))
+}
+
+impl Token<'_> {
+    /// The value of an integer literal token, or `None` for other tokens.
+    pub fn int_value(&self) -> Option<u128> {
+        match self.kind {
+            TokenKind::Int { base, suffix } => {
+                let text = self.text;
+                let end = text.len() - suffix.map_or(0, |ty| ty.as_str().len());
+                parse_digits(&text[base.prefix_len()..end], base)
+            }
+            _ => None,
+        }
+    }
+
+    /// The value of a float literal token.
+    pub fn float_value(&self) -> Option<f64> {
+        match self.kind {
+            TokenKind::Float { suffix } => {
+                let text = &self.text[..self.text.len() - suffix.map_or(0, |ty| ty.as_str().len())];
+                text.replace('_', "").parse().ok()
+            }
+            _ => None,
+        }
+    }
+
+    /// The value of a duration literal token.
+    pub fn duration_value(&self) -> Option<Duration> {
+        match self.kind {
+            TokenKind::Duration(unit) => {
+                let digits = &self.text[..self.text.len() - unit.as_str().len()];
+                let n = parse_digits(digits, Base::Decimal)? as u64;
+                Some(Duration::from_nanos(n * unit.nanos()))
+            }
+            _ => None,
+        }
+    }
+
+    /// The number of bytes a size literal token stands for.
+    pub fn size_value(&self) -> Option<u64> {
+        match self.kind {
+            TokenKind::Size(unit) => {
+                let digits = &self.text[..self.text.len() - unit.as_str().len()];
+                let n = parse_digits(digits, Base::Decimal)? as u64;
+                Some(n * unit.bytes())
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tokenize, Lexer};
+
+    fn int(src: &str) -> (TokenKind, u128) {
+        let tokens = tokenize(src);
+        assert_eq!(tokens.len(), 2, "{:?}", tokens);
+        (tokens[0].kind, tokens[0].int_value().unwrap())
+    }
+
+    fn error(src: &str) -> (LexErrorKind, String) {
+        let err = Lexer::new(src)
+            .find_map(Result::err)
+            .expect("expected a lex error");
+        (err.kind, src[err.span.range()].to_string())
+    }
+
+    #[test]
+    fn bases_and_separators() {
+        let dec = TokenKind::Int {
+            base: Base::Decimal,
+            suffix: None,
+        };
+        assert_eq!(int("1024"), (dec, 1024));
+        assert_eq!(int("1_000_000"), (dec, 1_000_000));
+        assert_eq!(int("0"), (dec, 0));
+        assert_eq!(int("0x7f").1, 0x7f);
+        assert_eq!(int("0o755").1, 0o755);
+        assert_eq!(int("0b1010_1010").1, 0b1010_1010);
+        assert_eq!(int("0xDEAD_beef").1, 0xdead_beef);
+    }
+
+    #[test]
+    fn suffixes() {
+        assert_eq!(
+            int("0u8"),
+            (
+                TokenKind::Int {
+                    base: Base::Decimal,
+                    suffix: Some(IntTy::U8)
+                },
+                0
+            )
+        );
+        assert_eq!(
+            int("0xffi32").0,
+            TokenKind::Int {
+                base: Base::Hexadecimal,
+                suffix: Some(IntTy::I32)
+            }
+        );
+        assert_eq!(int("128i8").1, 128);
+        assert_eq!(int("1_usize").1, 1);
+    }
+
+    #[test]
+    fn floats() {
+        let float = |src: &str| {
+            let tokens = tokenize(src);
+            assert_eq!(tokens.len(), 2, "{:?}", tokens);
+            (tokens[0].kind, tokens[0].float_value().unwrap())
+        };
+        assert_eq!(float("1.5"), (TokenKind::Float { suffix: None }, 1.5));
+        assert_eq!(float("1e-9").1, 1e-9);
+        assert_eq!(float("1_000.25E+3").1, 1_000.25e3);
+        assert_eq!(
+            float("2.0f32"),
+            (
+                TokenKind::Float {
+                    suffix: Some(FloatTy::F32)
+                },
+                2.0
+            )
+        );
+        assert_eq!(float("2f64").1, 2.0);
+        assert_eq!(float("2.").1, 2.0);
+        assert_eq!(error("1.5u8"), (LexErrorKind::InvalidSuffix, "u8".into()));
+        assert_eq!(error("1e"), (LexErrorKind::InvalidSuffix, "e".into()));
+    }
+
+    #[test]
+    fn float_disambiguation() {
+        use TokenKind::*;
+        let kinds = |src: &str| -> Vec<_> { tokenize(src).iter().map(|t| t.kind).collect() };
+        let int = Int {
+            base: Base::Decimal,
+            suffix: None,
+        };
+        assert_eq!(kinds("0..n"), vec![int, DotDot, Ident, Eof]);
+        assert_eq!(kinds("x.0"), vec![Ident, Dot, int, Eof]);
+        assert_eq!(
+            kinds("1.foo()"),
+            vec![int, Dot, Ident, ParenOpen, ParenClose, Eof]
+        );
+        assert_eq!(
+            kinds("1.0.max(2.)"),
+            vec![
+                Float { suffix: None },
+                Dot,
+                Ident,
+                ParenOpen,
+                Float { suffix: None },
+                ParenClose,
+                Eof
+            ]
+        );
+        assert_eq!(
+            kinds("[2.; 2.e3]"),
+            vec![
+                BracketOpen,
+                Float { suffix: None },
+                Semicolon,
+                int,
+                Dot,
+                Ident,
+                BracketClose,
+                Eof
+            ]
+        );
+        assert_eq!(kinds("2."), vec![Float { suffix: None }, Eof]);
+        assert_eq!(
+            kinds("0x1.5"),
+            vec![
+                Int {
+                    base: Base::Hexadecimal,
+                    suffix: None
+                },
+                Dot,
+                int,
+                Eof
+            ]
+        );
+    }
+
+    #[test]
+    fn durations_and_sizes() {
+        let tokens = tokenize("3s 250ms 10us 5ns 1_000ms 4KiB 1MiB 2GiB 1TiB");
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Duration(TimeUnit::Secs),
+                TokenKind::Duration(TimeUnit::Millis),
+                TokenKind::Duration(TimeUnit::Micros),
+                TokenKind::Duration(TimeUnit::Nanos),
+                TokenKind::Duration(TimeUnit::Millis),
+                TokenKind::Size(SizeUnit::KiB),
+                TokenKind::Size(SizeUnit::MiB),
+                TokenKind::Size(SizeUnit::GiB),
+                TokenKind::Size(SizeUnit::TiB),
+                TokenKind::Eof,
+            ]
+        );
+        assert_eq!(tokens[0].duration_value(), Some(Duration::from_secs(3)));
+        assert_eq!(tokens[1].duration_value(), Some(Duration::from_millis(250)));
+        assert_eq!(tokens[4].duration_value(), Some(Duration::from_secs(1)));
+        assert_eq!(tokens[5].size_value(), Some(4096));
+        assert_eq!(tokens[8].size_value(), Some(1 << 40));
+        assert_eq!(tokens[0].int_value(), None);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(error("256u8"), (LexErrorKind::IntOverflow, "256u8".into()));
+        assert_eq!(error("129i8").0, LexErrorKind::IntOverflow);
+        assert_eq!(
+            error("340282366920938463463374607431768211456").0,
+            LexErrorKind::IntOverflow
+        );
+        assert_eq!(
+            error("0b102"),
+            (LexErrorKind::InvalidDigit(Base::Binary), "2".into())
+        );
+        assert_eq!(
+            error("0o8"),
+            (LexErrorKind::InvalidDigit(Base::Octal), "8".into())
+        );
+        assert_eq!(error("0x"), (LexErrorKind::EmptyInt, "0x".into()));
+        assert_eq!(error("0x_u8").0, LexErrorKind::EmptyInt);
+        assert_eq!(error("12abc"), (LexErrorKind::InvalidSuffix, "abc".into()));
+        assert_eq!(error("0x10s"), (LexErrorKind::InvalidSuffix, "s".into()));
+        assert_eq!(error("3sec"), (LexErrorKind::InvalidSuffix, "sec".into()));
+        assert_eq!(error("18446744074s").0, LexErrorKind::IntOverflow);
+        assert_eq!(error("16777216TiB").0, LexErrorKind::IntOverflow);
+    }
+}
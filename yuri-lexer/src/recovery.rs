@@ -0,0 +1,95 @@
+//! Deciding how much input to skip after a lexical error so that lexing can
+//! continue.
+
+use crate::scan::{self, ScanError};
+use crate::LexErrorKind;
+
+/// Length of the run of characters at the start of `text` that cannot start
+/// any token. Stops at whitespace so that unrelated garbage is reported
+/// separately.
+pub(crate) fn unexpected_run(text: &str) -> usize {
+    let mut chars = text.char_indices().skip(1);
+    loop {
+        match chars.next() {
+            None => return text.len(),
+            Some((i, c)) => {
+                if c.is_whitespace() {
+                    return i;
+                }
+                if !matches!(scan::token(&text[i..]), Err(ScanError::Unexpected)) {
+                    return i;
+                }
+            }
+        }
+    }
+}
+
+/// Length of the malformed token starting at `text` that an error of `kind`
+/// ending at byte `error_end` was reported in.
+pub(crate) fn failure_len(text: &str, kind: LexErrorKind, error_end: usize) -> usize {
+    let extent = match kind {
+        // Everything up to the end of input belongs to the unterminated token.
+        LexErrorKind::UnterminatedLiteral | LexErrorKind::UnterminatedBlockComment => text.len(),
+        _ => literal_extent(text),
+    };
+    extent.max(error_end)
+}
+
+/// A lenient approximation of how far the literal or word at the start of
+/// `text` extends, ignoring whatever made it invalid.
+fn literal_extent(text: &str) -> usize {
+    let body_start = if text.starts_with('"') || text.starts_with('\'') {
+        1
+    } else if text.starts_with("b\"") || text.starts_with("b'") {
+        2
+    } else {
+        return raw_str_extent(text).unwrap_or_else(|| word_extent(text));
+    };
+    let quote = text.as_bytes()[body_start - 1];
+    let bytes = text.as_bytes();
+    let mut i = body_start;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'\n' if quote == b'\'' => return i,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    if quote == b'\'' {
+        body_start
+    } else {
+        text.len()
+    }
+}
+
+fn raw_str_extent(text: &str) -> Option<usize> {
+    let prefix = if text.starts_with("br") {
+        2
+    } else if text.starts_with('r') {
+        1
+    } else {
+        return None;
+    };
+    let hashes = text[prefix..].bytes().take_while(|&b| b == b'#').count();
+    if !text[prefix + hashes..].starts_with('"') {
+        return None;
+    }
+    let open = prefix + hashes + 1;
+    let close = text[open..]
+        .match_indices('"')
+        .map(|(i, _)| open + i + 1)
+        .find(|&i| {
+            text.as_bytes()[i..]
+                .iter()
+                .take_while(|&&b| b == b'#')
+                .count()
+                >= hashes
+        });
+    Some(close.map_or(text.len(), |i| i + hashes))
+}
+
+fn word_extent(text: &str) -> usize {
+    text.find(|c: char| !c.is_alphanumeric() && c != '_')
+        .unwrap_or(text.len())
+}
@@ -0,0 +1,189 @@
+//! Relexing a file after an edit without lexing all of it again.
+
+use std::ops::Range;
+
+use crate::{Lexer, Span, Token};
+
+/// How many bytes past its end lexing a token may look at, e.g. to tell `1.5`
+/// from `1.foo`. Lookahead beyond that never crosses a newline.
+const LOOKAHEAD: usize = 2;
+
+/// Replaces the byte range `range` of a source with `text`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> Self {
+        TextEdit {
+            range,
+            text: text.into(),
+        }
+    }
+
+    pub fn apply(&self, src: &str) -> String {
+        let mut out = String::with_capacity(src.len() - self.range.len() + self.text.len());
+        out.push_str(&src[..self.range.start]);
+        out.push_str(&self.text);
+        out.push_str(&src[self.range.end..]);
+        out
+    }
+}
+
+/// `token` moved by `delta` bytes into `src`.
+fn rebase<'a>(token: &Token<'_>, src: &'a str, delta: isize) -> Token<'a> {
+    let lo = (token.span.lo as isize + delta) as u32;
+    let hi = (token.span.hi as isize + delta) as u32;
+    Token {
+        kind: token.kind,
+        span: Span::new(token.span.file, lo, hi),
+        text: &src[lo as usize..hi as usize],
+        symbol: token.symbol,
+    }
+}
+
+impl<'a> Lexer<'a> {
+    /// Lexes the source of this lexer given `old`, the tokens of the source
+    /// before `edit` was applied to it, lexing only around the edit.
+    ///
+    /// `old` must have been produced by a lexer configured like this one, and
+    /// like `tokenize` the result leaves out errors.
+    pub fn relex(mut self, old: &[Token<'_>], edit: &TextEdit) -> Vec<Token<'a>> {
+        let src = self.src();
+        let old_end = edit.range.end;
+        let new_end = edit.range.start + edit.text.len();
+        let delta = new_end as isize - old_end as isize;
+        debug_assert_eq!(
+            old.last().map(|t| t.span.hi as isize + delta),
+            Some(src.len() as isize),
+        );
+
+        // Tokens ending well before the edited line cannot have seen the edit.
+        let line_start = src[..edit.range.start].rfind('\n').map_or(0, |i| i + 1);
+        let keep = old
+            .iter()
+            .take_while(|t| t.span.hi as usize + LOOKAHEAD < line_start)
+            .count();
+        let mut tokens: Vec<_> = old[..keep].iter().map(|t| rebase(t, src, 0)).collect();
+        self.seek(tokens.last().map_or(0, |t| t.span.hi as usize));
+
+        let mut rest = old[keep..].iter().peekable();
+        for token in self.filter_map(Result::ok) {
+            let lo = token.span.lo as isize;
+            while let Some(old) = rest.peek() {
+                if (old.span.lo as usize) < old_end || (old.span.lo as isize + delta) < lo {
+                    rest.next();
+                } else {
+                    break;
+                }
+            }
+            // Past the edit, the same token at the same place means the rest
+            // of the old tokens are still valid.
+            let resynced = token.span.lo as usize >= new_end
+                && rest.peek().is_some_and(|old| {
+                    old.kind == token.kind
+                        && old.span.lo as isize + delta == lo
+                        && old.span.len() == token.span.len()
+                });
+            if resynced {
+                tokens.extend(rest.map(|t| rebase(t, src, delta)));
+                return tokens;
+            }
+            tokens.push(token);
+        }
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use proptest::sample::Index;
+
+    use super::*;
+    use crate::{next_token, tokenize, tokenize_lossless, tokenize_with_errors, FileId, Input};
+
+    /// The tokens of `src` from a full pass of `next_token`, without `Eof`.
+    fn nom_tokens(src: &str) -> Vec<Token<'_>> {
+        let mut rest = Input::new_extra(src, FileId::default());
+        let mut tokens = Vec::new();
+        while let Ok((next, token)) = next_token(rest) {
+            tokens.push(token);
+            rest = next;
+        }
+        tokens
+    }
+
+    fn check(src: &str, edit: &TextEdit) {
+        let new_src = edit.apply(src);
+        let relexed = Lexer::new(&new_src).relex(&tokenize(src), edit);
+        assert_eq!(relexed, tokenize(&new_src), "{:?} {:?}", src, edit);
+        if tokenize_with_errors(&new_src).1.is_empty() {
+            assert_eq!(relexed[..relexed.len() - 1], nom_tokens(&new_src)[..]);
+        }
+        let relexed = Lexer::lossless(&new_src).relex(&tokenize_lossless(src), edit);
+        assert_eq!(relexed, tokenize_lossless(&new_src));
+    }
+
+    #[test]
+    fn relex() {
+        check("let x = 1;\nlet y = 2;", &TextEdit::new(4..5, "xs"));
+        check("a = b", &TextEdit::new(3..3, "="));
+        check("a /* b */ c", &TextEdit::new(6..6, "*/ d /*"));
+        check("r###x\n", &TextEdit::new(4..5, "\"x\"###"));
+        check("1 .5", &TextEdit::new(1..2, ""));
+        check("x\ny", &TextEdit::new(0..3, ""));
+        check("", &TextEdit::new(0..0, "fn main() {}"));
+    }
+
+    #[test]
+    fn reuses_tokens_after_the_edit() {
+        let src = "fn a() {}\nfn b() {}\nfn c() {}";
+        let old = tokenize(src);
+        let edit = TextEdit::new(13..14, "bb");
+        let new_src = edit.apply(src);
+        let relexed = Lexer::new(&new_src).relex(&old, &edit);
+        assert_eq!(relexed, tokenize(&new_src));
+        assert_eq!(
+            relexed[relexed.len() - 2].span.lo,
+            old[old.len() - 2].span.lo + 1
+        );
+    }
+
+    fn fragment() -> impl Strategy<Value = &'static str> {
+        prop::sample::select(vec![
+            "fn", "x", "r#match", "r#", "#", "(", ")", "{", "}", ":", "::", ".", "..", "=", "==",
+            "0", "1.5", "1e", "-", "3s", "\"", "\"s\"", "'", "'c'", "/*", "*/", "//", "/", "*",
+            "!", " ", "\n", "ユ",
+        ])
+    }
+
+    fn source() -> impl Strategy<Value = String> {
+        prop::collection::vec(fragment(), 0..32).prop_map(|f| f.concat())
+    }
+
+    /// The char boundary of `src` at or before `index`.
+    fn boundary(src: &str, index: Index) -> usize {
+        let mut i = index.index(src.len() + 1);
+        while !src.is_char_boundary(i) {
+            i -= 1;
+        }
+        i
+    }
+
+    proptest! {
+        #[test]
+        fn relex_matches_full_pass(
+            src in source(),
+            a in any::<Index>(),
+            b in any::<Index>(),
+            text in source(),
+        ) {
+            let (a, b) = (boundary(&src, a), boundary(&src, b));
+            let edit = TextEdit::new(a.min(b)..a.max(b), text);
+            check(&src, &edit);
+        }
+    }
+}
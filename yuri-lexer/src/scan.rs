@@ -0,0 +1,516 @@
+//! The hand-written lexer behind `Lexer`.
+//!
+//! Every function here looks at the start of the remaining input and returns
+//! the kind and length of the token found there. The first byte selects the
+//! scanner through a class table, so no token is ever tried and backtracked.
+//! The nom parsers in the other modules are the reference implementation that
+//! this lexer must agree with.
+
+use std::ops::Range;
+
+use crate::ident::{is_ident_continue, is_ident_start};
+use crate::literal::{float_tail, number_kind};
+use crate::string::{scan_escape, Mode};
+use crate::{Base, DocStyle, Keyword, LexErrorKind, TokenKind, RESERVED};
+
+pub(crate) enum ScanError {
+    /// No token starts here.
+    Unexpected,
+    /// A malformed token. The range is relative to the start of the input.
+    Invalid(LexErrorKind, Range<usize>),
+}
+
+pub(crate) type Scan = Result<(TokenKind, usize), ScanError>;
+
+fn invalid(kind: LexErrorKind, range: Range<usize>) -> Scan {
+    Err(ScanError::Invalid(kind, range))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Other,
+    Space,
+    Ident,
+    /// `r`, which may start a raw string or raw identifier.
+    R,
+    /// `b`, which may start a byte (string) literal.
+    B,
+    Digit,
+    Quote,
+    Apostrophe,
+    Punct,
+    NonAscii,
+}
+
+const fn classify(b: u8) -> Class {
+    match b {
+        b' ' | b'\t' | b'\n' | b'\r' => Class::Space,
+        b'r' => Class::R,
+        b'b' => Class::B,
+        b'a'..=b'z' | b'A'..=b'Z' | b'_' => Class::Ident,
+        b'0'..=b'9' => Class::Digit,
+        b'"' => Class::Quote,
+        b'\'' => Class::Apostrophe,
+        b'(' | b')' | b'{' | b'}' | b'[' | b']' | b':' | b';' | b',' | b'.' | b'?' | b'#'
+        | b'@' | b'=' | b'!' | b'<' | b'>' | b'+' | b'-' | b'*' | b'/' | b'%' | b'^' | b'&'
+        | b'|' => Class::Punct,
+        0x80..=0xff => Class::NonAscii,
+        _ => Class::Other,
+    }
+}
+
+static CLASSES: [Class; 256] = {
+    let mut table = [Class::Other; 256];
+    let mut b = 0;
+    while b < 256 {
+        table[b] = classify(b as u8);
+        b += 1;
+    }
+    table
+};
+
+fn class(b: u8) -> Class {
+    CLASSES[usize::from(b)]
+}
+
+/// Length of the line starting at `bytes`, excluding the newline.
+fn line_len(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .position(|&b| b == b'\n')
+        .unwrap_or(bytes.len())
+}
+
+/// The style of the doc comment starting at `bytes`, if any. `////` is an
+/// ordinary comment.
+fn doc_style(bytes: &[u8]) -> Option<DocStyle> {
+    if !bytes.starts_with(b"//") {
+        return None;
+    }
+    match bytes.get(2) {
+        Some(b'!') => Some(DocStyle::Inner),
+        Some(b'/') if bytes.get(3) != Some(&b'/') => Some(DocStyle::Outer),
+        _ => None,
+    }
+}
+
+fn block_comment(bytes: &[u8]) -> Scan {
+    let mut depth = 0usize;
+    let mut i = 0;
+    while i + 1 < bytes.len() {
+        match (bytes[i], bytes[i + 1]) {
+            (b'/', b'*') => {
+                depth += 1;
+                i += 2;
+            }
+            (b'*', b'/') => {
+                depth -= 1;
+                i += 2;
+                if depth == 0 {
+                    return Ok((TokenKind::BlockComment, i));
+                }
+            }
+            _ => i += 1,
+        }
+    }
+    invalid(LexErrorKind::UnterminatedBlockComment, 0..2)
+}
+
+/// Whitespace or an ordinary comment.
+pub(crate) fn trivia(text: &str) -> Scan {
+    let bytes = text.as_bytes();
+    match bytes.first() {
+        Some(&b) if class(b) == Class::Space => {
+            let len = bytes
+                .iter()
+                .position(|&b| class(b) != Class::Space)
+                .unwrap_or(bytes.len());
+            Ok((TokenKind::Whitespace, len))
+        }
+        Some(b'/') => match bytes.get(1) {
+            Some(b'/') if doc_style(bytes).is_none() => {
+                Ok((TokenKind::LineComment, line_len(bytes)))
+            }
+            Some(b'*') => block_comment(bytes),
+            _ => Err(ScanError::Unexpected),
+        },
+        _ => Err(ScanError::Unexpected),
+    }
+}
+
+/// A single token other than trivia.
+pub(crate) fn token(text: &str) -> Scan {
+    let bytes = text.as_bytes();
+    let b = match bytes.first() {
+        Some(&b) => b,
+        None => return Err(ScanError::Unexpected),
+    };
+    match class(b) {
+        Class::Punct => punct(bytes),
+        Class::Digit => int(text),
+        Class::Quote => quoted(text, 1, Mode::Str, TokenKind::Str),
+        Class::Apostrophe => {
+            lifetime(text).unwrap_or_else(|| char_lit(text, 1, Mode::Char, TokenKind::Char))
+        }
+        Class::R => raw_str(text, 1).unwrap_or_else(|| raw_ident(text)),
+        Class::B => match bytes.get(1) {
+            Some(b'r') => raw_str(text, 2).unwrap_or_else(|| ident(text)),
+            Some(b'"') => quoted(text, 2, Mode::ByteStr, TokenKind::ByteStr),
+            Some(b'\'') => char_lit(text, 2, Mode::Byte, TokenKind::Byte),
+            _ => ident(text),
+        },
+        Class::Ident | Class::NonAscii => ident(text),
+        Class::Space | Class::Other => Err(ScanError::Unexpected),
+    }
+}
+
+fn punct(bytes: &[u8]) -> Scan {
+    use TokenKind::*;
+    if let Some(style) = doc_style(bytes) {
+        return Ok((DocComment(style), line_len(bytes)));
+    }
+    let at = |i: usize| bytes.get(i).copied().unwrap_or(0);
+    let kind = match (bytes[0], at(1)) {
+        (b'.', b'.') if at(2) == b'=' => DotDotEqual,
+        (b'<', b'<') if at(2) == b'=' => LessLessEqual,
+        (b'>', b'>') if at(2) == b'=' => GreaterGreaterEqual,
+        (b':', b':') => ColonColon,
+        (b'.', b'.') => DotDot,
+        (b'-', b'>') => Arrow,
+        (b'=', b'>') => FatArrow,
+        (b'=', b'=') => EqualEqual,
+        (b'!', b'=') => BangEqual,
+        (b'<', b'=') => LessEqual,
+        (b'>', b'=') => GreaterEqual,
+        (b'+', b'=') => PlusEqual,
+        (b'-', b'=') => MinusEqual,
+        (b'*', b'=') => StarEqual,
+        (b'/', b'=') => SlashEqual,
+        (b'%', b'=') => PercentEqual,
+        (b'^', b'=') => CaretEqual,
+        (b'&', b'&') => AmpAmp,
+        (b'&', b'=') => AmpEqual,
+        (b'|', b'|') => PipePipe,
+        (b'|', b'=') => PipeEqual,
+        (b'<', b'<') => LessLess,
+        (b'>', b'>') => GreaterGreater,
+        (b'(', _) => ParenOpen,
+        (b')', _) => ParenClose,
+        (b'{', _) => BraceOpen,
+        (b'}', _) => BraceClose,
+        (b'[', _) => BracketOpen,
+        (b']', _) => BracketClose,
+        (b':', _) => Colon,
+        (b';', _) => Semicolon,
+        (b',', _) => Comma,
+        (b'.', _) => Dot,
+        (b'?', _) => Question,
+        (b'#', _) => Pound,
+        (b'@', _) => At,
+        (b'=', _) => Equal,
+        (b'!', _) => Bang,
+        (b'<', _) => Less,
+        (b'>', _) => Greater,
+        (b'+', _) => Plus,
+        (b'-', _) => Minus,
+        (b'*', _) => Star,
+        (b'/', _) => Slash,
+        (b'%', _) => Percent,
+        (b'^', _) => Caret,
+        (b'&', _) => Amp,
+        (b'|', _) => Pipe,
+        _ => return Err(ScanError::Unexpected),
+    };
+    let len = match kind {
+        DotDotEqual | LessLessEqual | GreaterGreaterEqual => 3,
+        ParenOpen | ParenClose | BraceOpen | BraceClose | BracketOpen | BracketClose | Colon
+        | Semicolon | Comma | Dot | Question | Pound | At | Equal | Bang | Less | Greater
+        | Plus | Minus | Star | Slash | Percent | Caret | Amp | Pipe => 1,
+        _ => 2,
+    };
+    Ok((kind, len))
+}
+
+fn int(text: &str) -> Scan {
+    let bytes = text.as_bytes();
+    let (base, start) = match (bytes[0], bytes.get(1)) {
+        (b'0', Some(b'x')) => (Base::Hexadecimal, 2),
+        (b'0', Some(b'o')) => (Base::Octal, 2),
+        (b'0', Some(b'b')) => (Base::Binary, 2),
+        _ => (Base::Decimal, 0),
+    };
+    let is_digit = |b: u8| {
+        b == b'_'
+            || if base == Base::Hexadecimal {
+                b.is_ascii_hexdigit()
+            } else {
+                b.is_ascii_digit()
+            }
+    };
+    let digits_end = start + bytes[start..].iter().take_while(|&&b| is_digit(b)).count();
+    let tail = if base == Base::Decimal {
+        float_tail(&text[digits_end..])
+    } else {
+        0
+    };
+    let suffix_start = digits_end + tail;
+    let end = text[suffix_start..]
+        .find(|c: char| !c.is_alphanumeric() && c != '_')
+        .map_or(text.len(), |i| suffix_start + i);
+
+    let digits = &text[start..digits_end];
+    if digits.bytes().all(|b| b == b'_') {
+        return invalid(LexErrorKind::EmptyInt, 0..end);
+    }
+    if let Some(i) = digits.find(|c: char| c != '_' && c.to_digit(base.radix()).is_none()) {
+        return invalid(LexErrorKind::InvalidDigit(base), start + i..start + i + 1);
+    }
+    match number_kind(base, digits, tail > 0, &text[suffix_start..end]) {
+        Ok(kind) => Ok((kind, end)),
+        Err(LexErrorKind::InvalidSuffix) => invalid(LexErrorKind::InvalidSuffix, suffix_start..end),
+        Err(kind) => invalid(kind, 0..end),
+    }
+}
+
+/// The body of a `"`-delimited literal whose opening quote ends at `start`.
+fn quoted(text: &str, start: usize, mode: Mode, kind: TokenKind) -> Scan {
+    let bytes = text.as_bytes();
+    let mut i = start;
+    // Continuation bytes of multi-byte characters never equal `"` or `\`, so
+    // the body can be scanned a byte at a time.
+    while let Some(&b) = bytes.get(i) {
+        match b {
+            b'"' => return Ok((kind, i + 1)),
+            b'\\' => match scan_escape(&text[i + 1..], mode) {
+                Ok((_, len)) => i += 1 + len,
+                Err((kind, len)) => return invalid(kind, i..i + 1 + len),
+            },
+            0x80..=0xff if mode.is_byte() => {
+                let c = text[i..].chars().next().unwrap();
+                return invalid(LexErrorKind::NonAsciiByte, i..i + c.len_utf8());
+            }
+            _ => i += 1,
+        }
+    }
+    invalid(LexErrorKind::UnterminatedLiteral, 0..start)
+}
+
+/// A raw string whose `r` ends at `prefix`, or `None` if the input is not
+/// shaped like one.
+fn raw_str(text: &str, prefix: usize) -> Option<Scan> {
+    let bytes = text.as_bytes();
+    let hashes = bytes[prefix..].iter().take_while(|&&b| b == b'#').count();
+    if bytes.get(prefix + hashes) != Some(&b'"') {
+        return None;
+    }
+    let open = prefix + hashes + 1;
+    if hashes > usize::from(u8::MAX) {
+        return Some(invalid(LexErrorKind::TooManyHashes, 0..open));
+    }
+    let body_len = match text[open..].match_indices('"').find(|&(i, _)| {
+        let after = &bytes[open + i + 1..];
+        after.len() >= hashes && after[..hashes].iter().all(|&b| b == b'#')
+    }) {
+        Some((body_len, _)) => body_len,
+        None => return Some(invalid(LexErrorKind::UnterminatedLiteral, 0..open)),
+    };
+    let byte = prefix == 2;
+    if byte {
+        let body = &text[open..open + body_len];
+        if let Some(i) = body.find(|c: char| !c.is_ascii()) {
+            let c = body[i..].chars().next().unwrap();
+            return Some(invalid(
+                LexErrorKind::NonAsciiByte,
+                open + i..open + i + c.len_utf8(),
+            ));
+        }
+    }
+    let hashes = hashes as u8;
+    let kind = if byte {
+        TokenKind::RawByteStr { hashes }
+    } else {
+        TokenKind::RawStr { hashes }
+    };
+    Some(Ok((kind, open + body_len + 1 + usize::from(hashes))))
+}
+
+/// A lifetime label such as `'a`, or `None` if the `'` starts a character
+/// literal.
+fn lifetime(text: &str) -> Option<Scan> {
+    let len = 1 + ident_len(&text[1..])?;
+    if text[len..].starts_with('\'') {
+        return None;
+    }
+    Some(Ok((TokenKind::Lifetime, len)))
+}
+
+fn char_lit(text: &str, start: usize, mode: Mode, kind: TokenKind) -> Scan {
+    let body_len = match text[start..].chars().next() {
+        None => return invalid(LexErrorKind::UnterminatedLiteral, 0..start),
+        Some('\'') => return invalid(LexErrorKind::EmptyChar, 0..start + 1),
+        Some('\\') => match scan_escape(&text[start + 1..], mode) {
+            Ok((_, len)) => 1 + len,
+            Err((kind, len)) => return invalid(kind, start..start + 1 + len),
+        },
+        Some('\n') | Some('\t') => {
+            return invalid(LexErrorKind::UnescapedChar, start..start + 1);
+        }
+        Some(c) if mode.is_byte() && !c.is_ascii() => {
+            return invalid(LexErrorKind::NonAsciiByte, start..start + c.len_utf8());
+        }
+        Some(c) => c.len_utf8(),
+    };
+    let end = start + body_len;
+    if text[end..].starts_with('\'') {
+        return Ok((kind, end + 1));
+    }
+    let line_end = end + line_len(&text.as_bytes()[end..]);
+    match text[end..line_end].find('\'') {
+        Some(i) => invalid(LexErrorKind::OverlongChar, 0..end + i + 1),
+        None => invalid(LexErrorKind::UnterminatedLiteral, 0..start),
+    }
+}
+
+/// Length of the identifier at the start of `text`, if there is one.
+fn ident_len(text: &str) -> Option<usize> {
+    let first = text.chars().next()?;
+    if !is_ident_start(first) {
+        return None;
+    }
+    let bytes = text.as_bytes();
+    let mut i = first.len_utf8();
+    while let Some(&b) = bytes.get(i) {
+        if b.is_ascii() {
+            if !b.is_ascii_alphanumeric() && b != b'_' {
+                break;
+            }
+            i += 1;
+        } else {
+            let c = text[i..].chars().next().unwrap();
+            if !is_ident_continue(c) {
+                break;
+            }
+            i += c.len_utf8();
+        }
+    }
+    Some(i)
+}
+
+fn ident(text: &str) -> Scan {
+    let len = ident_len(text).ok_or(ScanError::Unexpected)?;
+    let name = &text[..len];
+    let kind = if name == "_" {
+        TokenKind::Underscore
+    } else if let Some(keyword) = Keyword::lookup(name) {
+        TokenKind::Keyword(keyword)
+    } else if RESERVED.contains(&name) {
+        return invalid(LexErrorKind::ReservedWord, 0..len);
+    } else {
+        TokenKind::Ident
+    };
+    Ok((kind, len))
+}
+
+fn raw_ident(text: &str) -> Scan {
+    let len = match text.strip_prefix("r#").and_then(ident_len) {
+        Some(len) => 2 + len,
+        None => return ident(text),
+    };
+    let name = &text[2..len];
+    if name == "_" || Keyword::lookup(name).is_some_and(Keyword::is_path_segment) {
+        return invalid(LexErrorKind::InvalidRawIdent, 0..len);
+    }
+    Ok((TokenKind::Ident, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::Slice;
+    use proptest::prelude::*;
+
+    use crate::comment::skip_trivia;
+    use crate::recovery::{failure_len, unexpected_run};
+    use crate::{FileId, Input, LexError, Lexer, Token};
+
+    use super::*;
+
+    /// The items the nom lexer produces for `src`, recovering from errors the
+    /// same way `Lexer` does.
+    fn nom_lex(src: &str) -> Vec<Result<Token<'_>, LexError>> {
+        let mut items = Vec::new();
+        let mut rest = Input::new_extra(src, FileId::default());
+        loop {
+            let (e, len) = match skip_trivia(rest) {
+                Ok((r, ())) => {
+                    rest = r;
+                    if rest.fragment().is_empty() {
+                        items.push(Ok(Token::new(TokenKind::Eof, rest)));
+                        return items;
+                    }
+                    match crate::token(rest) {
+                        Ok((r, token)) => {
+                            rest = r;
+                            items.push(Ok(token));
+                            continue;
+                        }
+                        Err(nom::Err::Error(_)) => {
+                            let len = unexpected_run(rest.fragment());
+                            let e = LexError::at(LexErrorKind::UnexpectedChar, rest.slice(..len));
+                            (e, len)
+                        }
+                        Err(nom::Err::Failure(e)) => {
+                            let end = e.span.hi as usize - rest.location_offset();
+                            (e, failure_len(rest.fragment(), e.kind, end))
+                        }
+                        Err(nom::Err::Incomplete(_)) => unreachable!(),
+                    }
+                }
+                Err(nom::Err::Failure(e)) => {
+                    rest = rest.slice(e.span.lo as usize - rest.location_offset()..);
+                    let end = e.span.hi as usize - rest.location_offset();
+                    (e, failure_len(rest.fragment(), e.kind, end))
+                }
+                Err(e) => panic!("unexpected trivia error {:?}", e),
+            };
+            items.push(Err(e));
+            items.push(Ok(Token::new(TokenKind::Error, rest.slice(..len))));
+            rest = rest.slice(len..);
+        }
+    }
+
+    fn assert_same(src: &str) {
+        let expected = nom_lex(src);
+        let actual: Vec<_> = Lexer::new(src).collect();
+        assert_eq!(actual, expected, "{:?}", src);
+    }
+
+    #[test]
+    fn matches_nom() {
+        let samples = [
+            "fn copy (src: Fd) (dest: Fd) {\n    let mut buf = [0u8; 1024];\n}",
+            "a.b..c..=d :: -> => <<= >>= &&= ||| //! x\n/// y\n//// z\n/**/ /",
+            "0x 0b102 0o8 12abc 256u8 1_000i32 0xffi32 1.foo() _ _x r#fn r#self r#_ r#1",
+            "\"a\\q\" b\"\\u{41}\" b\"ユ\" r#\"a\"# br\"x\" br#x r\"open",
+            "'' 'ab' 'a '\\n' b'\\xff' '\t' 'ユ' b'ユ'",
+            "&'a T 'static 'a'b '_' '_ 'ユ'x 'r#a '1",
+            "async バッファ ñandú $€ ` ~ \\ /* /* nested */ unterminated",
+        ];
+        for src in &samples {
+            assert_same(src);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn matches_nom_on_any_input(src in "\\PC*") {
+            assert_same(&src);
+        }
+
+        #[test]
+        fn matches_nom_on_token_soup(
+            src in "([a-z_0-9#\"'\\\\/*!{}()<>=.:&|+ \n-]|ユ|é){0,40}"
+        ) {
+            assert_same(&src);
+        }
+    }
+}
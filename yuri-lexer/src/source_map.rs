@@ -0,0 +1,306 @@
+use std::ops::Range;
+
+use crate::Input;
+
+/// Identifies a file in a `SourceMap`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileId(pub u32);
+
+/// A byte range `lo..hi` in a source file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub file: FileId,
+    pub lo: u32,
+    pub hi: u32,
+}
+
+impl Span {
+    pub fn new(file: FileId, lo: u32, hi: u32) -> Self {
+        debug_assert!(lo <= hi);
+        Span { file, lo, hi }
+    }
+
+    /// The span of a lexer input fragment.
+    pub fn of(input: &Input) -> Self {
+        let lo = input.location_offset() as u32;
+        Span::new(input.extra, lo, lo + input.fragment().len() as u32)
+    }
+
+    pub fn len(self) -> u32 {
+        self.hi - self.lo
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.lo == self.hi
+    }
+
+    pub fn range(self) -> Range<usize> {
+        self.lo as usize..self.hi as usize
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn to(self, other: Span) -> Span {
+        debug_assert_eq!(self.file, other.file);
+        Span::new(self.file, self.lo.min(other.lo), self.hi.max(other.hi))
+    }
+
+    pub fn contains(self, pos: u32) -> bool {
+        self.lo <= pos && pos < self.hi
+    }
+}
+
+/// A 1-based line and column.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineCol {
+    pub line: u32,
+    pub col: u32,
+}
+
+/// A character outside ASCII, which takes up more UTF-8 bytes than UTF-16
+/// units.
+#[derive(Clone, Copy, Debug)]
+struct WideChar {
+    pos: u32,
+    len: u8,
+    /// How many more UTF-8 bytes than UTF-16 units this and every earlier
+    /// wide character of the file take up together.
+    extra: u32,
+}
+
+/// The contents of a source file together with an index of its line starts.
+#[derive(Debug)]
+pub struct SourceFile {
+    id: FileId,
+    name: String,
+    src: String,
+    /// Byte offsets at which each line starts. Always begins with 0.
+    line_starts: Vec<u32>,
+    /// Every character outside ASCII, in order.
+    wide_chars: Vec<WideChar>,
+}
+
+impl SourceFile {
+    /// # Panics
+    ///
+    /// Panics if `src` is 4GiB or larger, since spans use 32-bit offsets.
+    pub fn new(id: FileId, name: impl Into<String>, src: impl Into<String>) -> Self {
+        let src = src.into();
+        assert!(
+            src.len() <= u32::MAX as usize,
+            "source files must be smaller than 4GiB"
+        );
+        let line_starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(i, _)| i as u32 + 1))
+            .collect();
+        let mut extra = 0;
+        let wide_chars = src
+            .char_indices()
+            .filter(|(_, c)| !c.is_ascii())
+            .map(|(pos, c)| {
+                extra += (c.len_utf8() - c.len_utf16()) as u32;
+                WideChar {
+                    pos: pos as u32,
+                    len: c.len_utf8() as u8,
+                    extra,
+                }
+            })
+            .collect();
+        SourceFile {
+            id,
+            name: name.into(),
+            src,
+            line_starts,
+            wide_chars,
+        }
+    }
+
+    pub fn id(&self) -> FileId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
+    /// The span of the whole file.
+    pub fn span(&self) -> Span {
+        Span::new(self.id, 0, self.src.len() as u32)
+    }
+
+    /// The lexer input for this file.
+    pub fn input(&self) -> Input<'_> {
+        Input::new_extra(&self.src, self.id)
+    }
+
+    pub fn text(&self, span: Span) -> &str {
+        debug_assert_eq!(span.file, self.id);
+        &self.src[span.range()]
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// The 0-based index of the line containing byte `pos`.
+    pub fn line_index(&self, pos: u32) -> usize {
+        match self.line_starts.binary_search(&pos) {
+            Ok(line) => line,
+            Err(next) => next - 1,
+        }
+    }
+
+    /// The byte range of the 0-based line `line`, excluding its `\n` or
+    /// `\r\n`.
+    pub fn line_range(&self, line: usize) -> Range<usize> {
+        let start = self.line_starts[line] as usize;
+        let end = match self.line_starts.get(line + 1) {
+            Some(&next) if self.src[..next as usize].ends_with("\r\n") => next as usize - 2,
+            Some(&next) => next as usize - 1,
+            None => self.src.len(),
+        };
+        start..end
+    }
+
+    /// The 1-based line and UTF-8 (byte) column of byte `pos`.
+    pub fn line_col(&self, pos: u32) -> LineCol {
+        let line = self.line_index(pos);
+        LineCol {
+            line: line as u32 + 1,
+            col: pos - self.line_starts[line] + 1,
+        }
+    }
+
+    /// The 1-based line and character column of byte `pos`, as shown to
+    /// people. A `pos` within a character counts as the start of that
+    /// character.
+    pub fn line_col_char(&self, pos: u32) -> LineCol {
+        let line = self.line_index(pos);
+        let start = self.line_starts[line] as usize;
+        let col = self.src[start..]
+            .char_indices()
+            .take_while(|&(i, c)| start + i + c.len_utf8() <= pos as usize)
+            .count();
+        LineCol {
+            line: line as u32 + 1,
+            col: col as u32 + 1,
+        }
+    }
+
+    /// The 1-based line and UTF-16 column of byte `pos`, as used by LSP. A
+    /// `pos` within a character counts as the start of that character.
+    pub fn line_col_utf16(&self, pos: u32) -> LineCol {
+        let line = self.line_index(pos);
+        let start = self.line_starts[line];
+        let mut next = self.wide_chars.partition_point(|c| c.pos < pos);
+        let mut pos = pos;
+        if let Some(c) = next.checked_sub(1).map(|i| self.wide_chars[i]) {
+            if pos < c.pos + u32::from(c.len) {
+                pos = c.pos;
+                next -= 1;
+            }
+        }
+        let extra = |next: usize| next.checked_sub(1).map_or(0, |i| self.wide_chars[i].extra);
+        let first = self.wide_chars.partition_point(|c| c.pos < start);
+        LineCol {
+            line: line as u32 + 1,
+            col: pos - start - (extra(next) - extra(first)) + 1,
+        }
+    }
+}
+
+/// Owns every source file of a compilation.
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap::default()
+    }
+
+    /// Adds a file. The first file gets `FileId(0)`, the id `Lexer::new` assumes.
+    pub fn add_file(&mut self, name: impl Into<String>, src: impl Into<String>) -> FileId {
+        let id = FileId(self.files.len() as u32);
+        self.files.push(SourceFile::new(id, name, src));
+        id
+    }
+
+    pub fn file(&self, id: FileId) -> &SourceFile {
+        &self.files[id.0 as usize]
+    }
+
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    pub fn text(&self, span: Span) -> &str {
+        self.file(span.file).text(span)
+    }
+
+    /// The 1-based line and UTF-8 column where `span` starts.
+    pub fn line_col(&self, span: Span) -> LineCol {
+        self.file(span.file).line_col(span.lo)
+    }
+
+    /// The 1-based line and UTF-16 column where `span` starts.
+    pub fn line_col_utf16(&self, span: Span) -> LineCol {
+        self.file(span.file).line_col_utf16(span.lo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_col() {
+        let file = SourceFile::new(FileId(0), "a.yuri", "ab\n\nバッファ😀x\n");
+        assert_eq!(file.line_count(), 4);
+        assert_eq!(file.line_col(0), LineCol { line: 1, col: 1 });
+        assert_eq!(file.line_col(2), LineCol { line: 1, col: 3 });
+        assert_eq!(file.line_col(3), LineCol { line: 2, col: 1 });
+        assert_eq!(file.line_col(4), LineCol { line: 3, col: 1 });
+        // バッファ is 12 bytes / 4 UTF-16 units, 😀 is 4 bytes / 2 units.
+        let x = file.src().find('x').unwrap() as u32;
+        assert_eq!(file.line_col(x), LineCol { line: 3, col: 17 });
+        assert_eq!(file.line_col_utf16(x), LineCol { line: 3, col: 7 });
+        assert_eq!(file.line_col_char(x), LineCol { line: 3, col: 6 });
+        assert_eq!(file.line_col(file.src().len() as u32).line, 4);
+        assert_eq!(&file.src()[file.line_range(2)], "バッファ😀x");
+        assert_eq!(file.line_range(3), 22..22);
+        // Within 😀, which starts at byte 16.
+        assert_eq!(file.line_col_utf16(17), LineCol { line: 3, col: 5 });
+        assert_eq!(file.line_col_char(17), LineCol { line: 3, col: 5 });
+    }
+
+    #[test]
+    fn crlf() {
+        let file = SourceFile::new(FileId(0), "a.yuri", "ab\r\nü\r\n\r");
+        assert_eq!(file.line_count(), 3);
+        assert_eq!(&file.src()[file.line_range(0)], "ab");
+        assert_eq!(&file.src()[file.line_range(1)], "ü");
+        assert_eq!(&file.src()[file.line_range(2)], "\r");
+        assert_eq!(file.line_col(4), LineCol { line: 2, col: 1 });
+        assert_eq!(file.line_col_utf16(6), LineCol { line: 2, col: 2 });
+    }
+
+    #[test]
+    fn source_map() {
+        let mut map = SourceMap::new();
+        let a = map.add_file("a.yuri", "let x = 1;");
+        let b = map.add_file("b.yuri", "fn\nmain");
+        assert_eq!(a, FileId(0));
+        let span = Span::new(b, 3, 7);
+        assert_eq!(map.text(span), "main");
+        assert_eq!(map.line_col(span), LineCol { line: 2, col: 1 });
+        assert_eq!(
+            map.file(a).span().to(Span::new(a, 4, 5)),
+            map.file(a).span()
+        );
+    }
+}
@@ -0,0 +1,428 @@
+use std::collections::VecDeque;
+use std::ops::Range;
+
+use crate::scan::{self, ScanError};
+use crate::{recovery, FileId, Intern, LexError, LexErrorKind, SourceFile, Span, Token, TokenKind};
+
+/// A stream of tokens over a source string.
+///
+/// The stream yields every token of the input followed by a single
+/// `TokenKind::Eof` token, after which it returns `None`.
+///
+/// Lexing continues past errors: each error is reported as an `Err` item that
+/// is immediately followed by a `TokenKind::Error` token covering the text that
+/// was skipped to recover from it.
+///
+/// A lossless lexer additionally yields whitespace and comments as trivia
+/// tokens, so the spans of its tokens cover the input without gaps.
+pub struct Lexer<'a> {
+    src: &'a str,
+    file: FileId,
+    /// Byte offset of the remaining input.
+    pos: usize,
+    lookahead: VecDeque<Result<Token<'a>, LexError>>,
+    /// The `Error` token to yield after the error it belongs to.
+    error_token: Option<Token<'a>>,
+    finished: bool,
+    trivia: bool,
+    interner: Option<&'a dyn Intern>,
+}
+
+impl<'a> Lexer<'a> {
+    /// A lexer for a source string. Its spans belong to `FileId(0)`.
+    pub fn new(src: &'a str) -> Self {
+        Lexer::with_file(src, FileId::default())
+    }
+
+    /// A lexer for a file in a `SourceMap`.
+    pub fn for_file(file: &'a SourceFile) -> Self {
+        Lexer::with_file(file.src(), file.id())
+    }
+
+    /// A lexer for a source string whose spans belong to `file`, e.g. part of
+    /// a file in a `SourceMap` that is being lexed again.
+    pub fn with_file(src: &'a str, file: FileId) -> Self {
+        Lexer {
+            src,
+            file,
+            pos: 0,
+            lookahead: VecDeque::new(),
+            error_token: None,
+            finished: false,
+            trivia: false,
+            interner: None,
+        }
+    }
+
+    /// A lexer for a source string that also yields trivia tokens.
+    pub fn lossless(src: &'a str) -> Self {
+        Lexer::new(src).with_trivia()
+    }
+
+    /// Makes this lexer yield trivia tokens too.
+    pub fn with_trivia(self) -> Self {
+        Lexer {
+            trivia: true,
+            ..self
+        }
+    }
+
+    /// Makes this lexer intern identifiers and literals into `interner` and
+    /// record their symbols in `Token::symbol`.
+    pub fn with_interner(self, interner: &'a dyn Intern) -> Self {
+        Lexer {
+            interner: Some(interner),
+            ..self
+        }
+    }
+
+    /// Returns the next item without consuming it.
+    pub fn peek(&mut self) -> Option<&Result<Token<'a>, LexError>> {
+        self.peek_nth(0)
+    }
+
+    /// Returns the `n`th upcoming item (0-based) without consuming anything.
+    pub fn peek_nth(&mut self, n: usize) -> Option<&Result<Token<'a>, LexError>> {
+        while self.lookahead.len() <= n {
+            let item = self.lex()?;
+            self.lookahead.push_back(item);
+        }
+        self.lookahead.get(n)
+    }
+
+    /// The source being lexed.
+    pub fn src(&self) -> &'a str {
+        self.src
+    }
+
+    /// Continues lexing at byte `pos`, which must be where a token ended.
+    pub(crate) fn seek(&mut self, pos: usize) {
+        debug_assert!(self.lookahead.is_empty() && self.error_token.is_none());
+        self.pos = pos;
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    /// The span of `range`, relative to the remaining input.
+    fn span(&self, range: Range<usize>) -> Span {
+        Span::new(
+            self.file,
+            (self.pos + range.start) as u32,
+            (self.pos + range.end) as u32,
+        )
+    }
+
+    /// Consumes the next `len` bytes as a token.
+    fn bump(&mut self, kind: TokenKind, len: usize) -> Token<'a> {
+        let mut token = Token {
+            kind,
+            span: self.span(0..len),
+            text: &self.rest()[..len],
+            symbol: None,
+        };
+        if let Some(interner) = self.interner {
+            token.symbol = token.symbol_str().map(|s| interner.intern(&s));
+        }
+        self.pos += len;
+        token
+    }
+
+    fn lex(&mut self) -> Option<Result<Token<'a>, LexError>> {
+        if let Some(token) = self.error_token.take() {
+            return Some(Ok(token));
+        }
+        if self.finished {
+            return None;
+        }
+
+        loop {
+            match scan::trivia(self.rest()) {
+                Ok((kind, len)) => {
+                    let token = self.bump(kind, len);
+                    if self.trivia {
+                        return Some(Ok(token));
+                    }
+                }
+                Err(ScanError::Unexpected) => break,
+                // An unterminated block comment, which swallows the rest of
+                // the input.
+                Err(ScanError::Invalid(kind, range)) => return Some(Err(self.fail(kind, range))),
+            }
+        }
+        if self.pos == self.src.len() {
+            self.finished = true;
+            return Some(Ok(self.bump(TokenKind::Eof, 0)));
+        }
+
+        match scan::token(self.rest()) {
+            Ok((kind, len)) => Some(Ok(self.bump(kind, len))),
+            Err(ScanError::Unexpected) => {
+                let len = recovery::unexpected_run(self.rest());
+                let e = LexError::new(LexErrorKind::UnexpectedChar, self.span(0..len));
+                Some(Err(self.recover(len, e)))
+            }
+            Err(ScanError::Invalid(kind, range)) => Some(Err(self.fail(kind, range))),
+        }
+    }
+
+    /// Reports a malformed token at the start of the remaining input.
+    fn fail(&mut self, kind: LexErrorKind, range: Range<usize>) -> LexError {
+        let e = LexError::new(kind, self.span(range.clone()));
+        let len = recovery::failure_len(self.rest(), kind, range.end);
+        self.recover(len, e)
+    }
+
+    /// Skips the first `len` bytes of the remaining input as an `Error` token
+    /// to be yielded after `e`.
+    fn recover(&mut self, len: usize, e: LexError) -> LexError {
+        self.error_token = Some(self.bump(TokenKind::Error, len));
+        e
+    }
+}
+
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<Token<'a>, LexError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.lookahead.pop_front() {
+            Some(item) => Some(item),
+            None => self.lex(),
+        }
+    }
+}
+
+/// Lexes the whole input, including the trailing `TokenKind::Eof`. Invalid
+/// input shows up as `TokenKind::Error` tokens.
+pub fn tokenize(src: &str) -> Vec<Token<'_>> {
+    Lexer::new(src).filter_map(Result::ok).collect()
+}
+
+/// Lexes the whole input, returning the tokens and every error separately.
+pub fn tokenize_with_errors(src: &str) -> (Vec<Token<'_>>, Vec<LexError>) {
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for item in Lexer::new(src) {
+        match item {
+            Ok(token) => tokens.push(token),
+            Err(e) => errors.push(e),
+        }
+    }
+    (tokens, errors)
+}
+
+/// Lexes the whole input including trivia. Concatenating the spans of the
+/// returned tokens reproduces the input.
+pub fn tokenize_lossless(src: &str) -> Vec<Token<'_>> {
+    Lexer::lossless(src).filter_map(Result::ok).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn kinds(src: &str) -> Vec<TokenKind> {
+        tokenize(src).iter().map(|t| t.kind).collect()
+    }
+
+    #[test]
+    fn ends_with_eof() {
+        use TokenKind::*;
+        assert_eq!(
+            kinds("fn foo (x) { }\n"),
+            vec![
+                Keyword(crate::Keyword::Fn),
+                Ident,
+                ParenOpen,
+                Ident,
+                ParenClose,
+                BraceOpen,
+                BraceClose,
+                Eof
+            ]
+        );
+        assert_eq!(kinds(""), vec![Eof]);
+        assert_eq!(kinds("  \n\t"), vec![Eof]);
+    }
+
+    #[test]
+    fn peek_does_not_consume() {
+        let mut lexer = Lexer::new("a + b");
+        assert_eq!(lexer.peek_nth(2).unwrap().unwrap().text, "b");
+        assert_eq!(lexer.peek().unwrap().unwrap().text, "a");
+        assert_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::Ident);
+        assert_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::Plus);
+        assert_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::Ident);
+        assert_eq!(lexer.next().unwrap().unwrap().kind, TokenKind::Eof);
+        assert!(lexer.next().is_none());
+        assert!(lexer.peek_nth(5).is_none());
+    }
+
+    #[test]
+    fn recovers_after_error() {
+        use TokenKind::*;
+        let src = "a $€ b";
+        let mut lexer = Lexer::new(src);
+        assert_eq!(lexer.next().unwrap().unwrap().kind, Ident);
+        let err = lexer.next().unwrap().unwrap_err();
+        assert_eq!(err.kind, LexErrorKind::UnexpectedChar);
+        assert_eq!(&src[err.span.range()], "$€");
+        assert_eq!(err.span.lo, 2);
+        let error = lexer.next().unwrap().unwrap();
+        assert_eq!(error.kind, Error);
+        assert_eq!(error.text, "$€");
+        assert_eq!(lexer.next().unwrap().unwrap().kind, Ident);
+        assert_eq!(lexer.next().unwrap().unwrap().kind, Eof);
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn reports_every_error() {
+        use TokenKind::*;
+        let src = "let s = \"\\q\"; $ 0x; 256u8 'ab' async\nok /* open";
+        let (tokens, errors) = tokenize_with_errors(src);
+        let errors: Vec<_> = errors.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            errors,
+            vec![
+                LexErrorKind::InvalidEscape,
+                LexErrorKind::UnexpectedChar,
+                LexErrorKind::EmptyInt,
+                LexErrorKind::IntOverflow,
+                LexErrorKind::OverlongChar,
+                LexErrorKind::ReservedWord,
+                LexErrorKind::UnterminatedBlockComment,
+            ]
+        );
+        let tokens: Vec<_> = tokens.iter().map(|t| (t.kind, t.text)).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                (Keyword(crate::Keyword::Let), "let"),
+                (Ident, "s"),
+                (Equal, "="),
+                (Error, "\"\\q\""),
+                (Semicolon, ";"),
+                (Error, "$"),
+                (Error, "0x"),
+                (Semicolon, ";"),
+                (Error, "256u8"),
+                (Error, "'ab'"),
+                (Error, "async"),
+                (Ident, "ok"),
+                (Error, "/* open"),
+                (Eof, ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn lossless_trivia() {
+        use TokenKind::*;
+        let tokens = tokenize_lossless("let x = 1; // one\n/* two */");
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Keyword(crate::Keyword::Let),
+                Whitespace,
+                Ident,
+                Whitespace,
+                Equal,
+                Whitespace,
+                Int {
+                    base: crate::Base::Decimal,
+                    suffix: None
+                },
+                Semicolon,
+                Whitespace,
+                LineComment,
+                Whitespace,
+                BlockComment,
+                Eof,
+            ]
+        );
+        assert_eq!(tokens.iter().filter(|t| t.kind.is_trivia()).count(), 7);
+    }
+
+    /// Checks that the tokens are contiguous and cover the whole input.
+    fn assert_lossless(src: &str) {
+        let mut end = 0;
+        for token in tokenize_lossless(src) {
+            assert_eq!(token.span.lo as usize, end);
+            end += token.text.len();
+        }
+        assert_eq!(end, src.len());
+    }
+
+    fn fragment() -> impl Strategy<Value = &'static str> {
+        prop::sample::select(vec![
+            "fn",
+            "copy",
+            "r#match",
+            "(",
+            ")",
+            "{",
+            "}",
+            "[",
+            "]",
+            ":",
+            "::",
+            ";",
+            ",",
+            ".",
+            "..",
+            "..=",
+            "&",
+            "&mut",
+            "=",
+            "==",
+            "=>",
+            "->",
+            "+",
+            "<<=",
+            "0",
+            "0u8",
+            "1024",
+            "0xff",
+            "1.5e-3",
+            "250ms",
+            "\"str\\n\"",
+            "b\"bytes\"",
+            "r#\"raw\"#",
+            "'c'",
+            "b'\\x7f'",
+            "/// doc",
+            "//! inner",
+            "// line",
+            "/* block */",
+            "/* /* nested */ */",
+            " ",
+            "\n",
+            "\t",
+            "\r\n",
+        ])
+    }
+
+    proptest! {
+        #[test]
+        fn lossless_round_trip(fragments in prop::collection::vec(fragment(), 0..64)) {
+            // Doc and line comments run to the end of the line, so always end
+            // them with a newline to keep the following fragments separate.
+            let src: String = fragments
+                .iter()
+                .map(|f| if f.starts_with("//") { format!("{}\n", f) } else { format!("{} ", f) })
+                .collect();
+            let tokens: Vec<_> = Lexer::lossless(&src).collect::<Result<_, _>>().unwrap();
+            let round_trip: String = tokens.iter().map(|t| t.text).collect();
+            prop_assert_eq!(round_trip, src);
+        }
+
+        #[test]
+        fn lossless_any_input(src in "\\PC*") {
+            assert_lossless(&src);
+        }
+    }
+}
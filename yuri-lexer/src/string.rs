@@ -0,0 +1,448 @@
+use std::borrow::Cow;
+
+use nom::{IResult, Slice};
+
+use crate::{Input, LexError, LexErrorKind, Token, TokenKind};
+
+/// What kind of quoted literal an escape sequence appears in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Mode {
+    Str,
+    ByteStr,
+    Char,
+    Byte,
+}
+
+impl Mode {
+    pub(crate) fn is_byte(self) -> bool {
+        self == Mode::ByteStr || self == Mode::Byte
+    }
+
+    fn allows_continuation(self) -> bool {
+        self == Mode::Str || self == Mode::ByteStr
+    }
+}
+
+/// Decodes the escape sequence at the start of `rest`, which begins right
+/// after a backslash.
+///
+/// Returns the decoded value (`None` for a line continuation) and the number of
+/// bytes consumed, or the error and the number of bytes it spans.
+pub(crate) fn scan_escape(
+    rest: &str,
+    mode: Mode,
+) -> Result<(Option<u32>, usize), (LexErrorKind, usize)> {
+    let c = match rest.chars().next() {
+        Some(c) => c,
+        None => return Err((LexErrorKind::InvalidEscape, 0)),
+    };
+    let simple = match c {
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        't' => Some('\t'),
+        '\\' => Some('\\'),
+        '0' => Some('\0'),
+        '\'' => Some('\''),
+        '"' => Some('"'),
+        _ => None,
+    };
+    if let Some(simple) = simple {
+        return Ok((Some(simple as u32), 1));
+    }
+
+    match c {
+        'x' => {
+            let hex = rest.get(1..3).unwrap_or("");
+            if hex.len() != 2 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                let len = 1 + rest[1..]
+                    .chars()
+                    .take(2)
+                    .take_while(char::is_ascii_hexdigit)
+                    .count();
+                return Err((LexErrorKind::InvalidEscape, len));
+            }
+            let value = u32::from_str_radix(hex, 16).unwrap();
+            if value > 0x7f && !mode.is_byte() {
+                return Err((LexErrorKind::OutOfRangeEscape, 3));
+            }
+            Ok((Some(value), 3))
+        }
+        'u' => {
+            if mode.is_byte() {
+                return Err((LexErrorKind::InvalidEscape, 1));
+            }
+            if !rest[1..].starts_with('{') {
+                return Err((LexErrorKind::InvalidEscape, 1));
+            }
+            let close = 2 + rest[2..]
+                .find(|c: char| !c.is_ascii_hexdigit() && c != '_')
+                .unwrap_or(rest.len() - 2);
+            if !rest[close..].starts_with('}') {
+                return Err((LexErrorKind::InvalidEscape, close));
+            }
+            let digits = &rest[2..close];
+            let valid = digits.chars().any(|c| c != '_')
+                && !digits.starts_with('_')
+                && digits.chars().filter(|&c| c != '_').count() <= 6;
+            if !valid {
+                return Err((LexErrorKind::InvalidEscape, close + 1));
+            }
+            let value = digits
+                .chars()
+                .filter(|&c| c != '_')
+                .fold(0, |acc, c| acc * 16 + c.to_digit(16).unwrap());
+            if std::char::from_u32(value).is_none() {
+                return Err((LexErrorKind::OutOfRangeEscape, close + 1));
+            }
+            Ok((Some(value), close + 1))
+        }
+        '\n' if mode.allows_continuation() => {
+            let skipped = rest[1..]
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(rest.len() - 1);
+            Ok((None, 1 + skipped))
+        }
+        c => Err((LexErrorKind::InvalidEscape, c.len_utf8())),
+    }
+}
+
+fn failure<T>(kind: LexErrorKind, span: Input) -> IResult<Input, T, LexError> {
+    Err(nom::Err::Failure(LexError::at(kind, span)))
+}
+
+fn not_this(s: Input) -> nom::Err<LexError> {
+    nom::Err::Error(LexError::at(
+        LexErrorKind::Nom(nom::error::ErrorKind::Tag),
+        s,
+    ))
+}
+
+/// Validates the body of a `"`-delimited literal starting at byte `start` of
+/// `s` and returns the index just past the closing quote.
+fn scan_quoted(s: Input, start: usize, mode: Mode) -> Result<usize, nom::Err<LexError>> {
+    let text = *s.fragment();
+    let mut i = start;
+    while let Some(c) = text[i..].chars().next() {
+        match c {
+            '"' => return Ok(i + 1),
+            '\\' => match scan_escape(&text[i + 1..], mode) {
+                Ok((_, len)) => i += 1 + len,
+                Err((kind, len)) => {
+                    return Err(nom::Err::Failure(LexError::at(
+                        kind,
+                        s.slice(i..i + 1 + len),
+                    )))
+                }
+            },
+            c if mode.is_byte() && !c.is_ascii() => {
+                return Err(nom::Err::Failure(LexError::at(
+                    LexErrorKind::NonAsciiByte,
+                    s.slice(i..i + c.len_utf8()),
+                )))
+            }
+            c => i += c.len_utf8(),
+        }
+    }
+    Err(nom::Err::Failure(LexError::at(
+        LexErrorKind::UnterminatedLiteral,
+        s.slice(..start),
+    )))
+}
+
+fn token(s: Input, len: usize, kind: TokenKind) -> IResult<Input, Token, LexError> {
+    Ok((s.slice(len..), Token::new(kind, s.slice(..len))))
+}
+
+/// `"..."` and `b"..."`.
+pub(crate) fn token_str(s: Input) -> IResult<Input, Token, LexError> {
+    let text = *s.fragment();
+    let (start, mode, kind) = if text.starts_with('"') {
+        (1, Mode::Str, TokenKind::Str)
+    } else if text.starts_with("b\"") {
+        (2, Mode::ByteStr, TokenKind::ByteStr)
+    } else {
+        return Err(not_this(s));
+    };
+    let len = scan_quoted(s, start, mode)?;
+    token(s, len, kind)
+}
+
+/// `r#"..."#` and `br#"..."#`, with any number of `#`s (including none).
+pub(crate) fn token_raw_str(s: Input) -> IResult<Input, Token, LexError> {
+    let text = *s.fragment();
+    let (prefix, byte) = if text.starts_with("br") {
+        (2, true)
+    } else if text.starts_with('r') {
+        (1, false)
+    } else {
+        return Err(not_this(s));
+    };
+    let hashes = text[prefix..].bytes().take_while(|&b| b == b'#').count();
+    if !text[prefix + hashes..].starts_with('"') {
+        // `r#ident` and plain identifiers starting with `r` are not ours.
+        return Err(not_this(s));
+    }
+    let open = prefix + hashes + 1;
+    if hashes > usize::from(u8::MAX) {
+        return failure(LexErrorKind::TooManyHashes, s.slice(..open));
+    }
+    let body_len = match text[open..].match_indices('"').find(|&(i, _)| {
+        let after = &text.as_bytes()[open + i + 1..];
+        after.len() >= hashes && after[..hashes].iter().all(|&b| b == b'#')
+    }) {
+        Some((body_len, _)) => body_len,
+        None => return failure(LexErrorKind::UnterminatedLiteral, s.slice(..open)),
+    };
+    let body = &text[open..open + body_len];
+    if byte {
+        if let Some(i) = body.find(|c: char| !c.is_ascii()) {
+            let c = body[i..].chars().next().unwrap();
+            return failure(
+                LexErrorKind::NonAsciiByte,
+                s.slice(open + i..open + i + c.len_utf8()),
+            );
+        }
+    }
+    let hashes = hashes as u8;
+    let kind = if byte {
+        TokenKind::RawByteStr { hashes }
+    } else {
+        TokenKind::RawStr { hashes }
+    };
+    token(s, open + body_len + 1 + usize::from(hashes), kind)
+}
+
+/// `'c'` and `b'c'`.
+pub(crate) fn token_char(s: Input) -> IResult<Input, Token, LexError> {
+    let text = *s.fragment();
+    let (start, mode, kind) = if text.starts_with('\'') {
+        (1, Mode::Char, TokenKind::Char)
+    } else if text.starts_with("b'") {
+        (2, Mode::Byte, TokenKind::Byte)
+    } else {
+        return Err(not_this(s));
+    };
+    let body_len = match text[start..].chars().next() {
+        None => return failure(LexErrorKind::UnterminatedLiteral, s.slice(..start)),
+        Some('\'') => return failure(LexErrorKind::EmptyChar, s.slice(..start + 1)),
+        Some('\\') => match scan_escape(&text[start + 1..], mode) {
+            Ok((_, len)) => 1 + len,
+            Err((kind, len)) => return failure(kind, s.slice(start..start + 1 + len)),
+        },
+        Some(c @ '\n') | Some(c @ '\t') => {
+            return failure(
+                LexErrorKind::UnescapedChar,
+                s.slice(start..start + c.len_utf8()),
+            )
+        }
+        Some(c) if mode.is_byte() && !c.is_ascii() => {
+            return failure(
+                LexErrorKind::NonAsciiByte,
+                s.slice(start..start + c.len_utf8()),
+            )
+        }
+        Some(c) => c.len_utf8(),
+    };
+    let end = start + body_len;
+    if text[end..].starts_with('\'') {
+        return token(s, end + 1, kind);
+    }
+    // Report `'ab'` as a single overlong literal rather than garbage.
+    let line_end = text[end..].find('\n').map_or(text.len(), |i| end + i);
+    match text[end..line_end].find('\'') {
+        Some(i) => failure(LexErrorKind::OverlongChar, s.slice(..end + i + 1)),
+        None => failure(LexErrorKind::UnterminatedLiteral, s.slice(..start)),
+    }
+}
+
+/// Decodes the escapes in the body of a validated string literal. Borrows the
+/// input unless it actually contains escapes.
+pub fn unescape_str(body: &str) -> Cow<'_, str> {
+    if !body.contains('\\') {
+        return Cow::Borrowed(body);
+    }
+    let mut out = String::with_capacity(body.len());
+    let mut i = 0;
+    while let Some(c) = body[i..].chars().next() {
+        if c == '\\' {
+            let (value, len) = scan_escape(&body[i + 1..], Mode::Str)
+                .expect("unescape_str called on an invalid literal");
+            out.extend(value.and_then(std::char::from_u32));
+            i += 1 + len;
+        } else {
+            out.push(c);
+            i += c.len_utf8();
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Decodes the escapes in the body of a validated byte string literal.
+/// Borrows the input unless it actually contains escapes.
+pub fn unescape_byte_str(body: &str) -> Cow<'_, [u8]> {
+    if !body.contains('\\') {
+        return Cow::Borrowed(body.as_bytes());
+    }
+    let mut out = Vec::with_capacity(body.len());
+    let mut i = 0;
+    while let Some(&b) = body.as_bytes().get(i) {
+        if b == b'\\' {
+            let (value, len) = scan_escape(&body[i + 1..], Mode::ByteStr)
+                .expect("unescape_byte_str called on an invalid literal");
+            out.extend(value.map(|v| v as u8));
+            i += 1 + len;
+        } else {
+            out.push(b);
+            i += 1;
+        }
+    }
+    Cow::Owned(out)
+}
+
+impl<'a> Token<'a> {
+    /// The contents of a string literal token with escapes decoded.
+    pub fn str_value(&self) -> Option<Cow<'a, str>> {
+        let text = self.text;
+        match self.kind {
+            TokenKind::Str => Some(unescape_str(&text[1..text.len() - 1])),
+            TokenKind::RawStr { hashes } => {
+                let hashes = usize::from(hashes);
+                Some(Cow::Borrowed(&text[2 + hashes..text.len() - 1 - hashes]))
+            }
+            _ => None,
+        }
+    }
+
+    /// The contents of a byte string literal token with escapes decoded.
+    pub fn byte_str_value(&self) -> Option<Cow<'a, [u8]>> {
+        let text = self.text;
+        match self.kind {
+            TokenKind::ByteStr => Some(unescape_byte_str(&text[2..text.len() - 1])),
+            TokenKind::RawByteStr { hashes } => {
+                let hashes = usize::from(hashes);
+                Some(Cow::Borrowed(
+                    &text.as_bytes()[3 + hashes..text.len() - 1 - hashes],
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// The value of a character literal token.
+    pub fn char_value(&self) -> Option<char> {
+        match self.kind {
+            TokenKind::Char => {
+                let text = self.text;
+                unescape_str(&text[1..text.len() - 1]).chars().next()
+            }
+            _ => None,
+        }
+    }
+
+    /// The value of a byte literal token.
+    pub fn byte_value(&self) -> Option<u8> {
+        match self.kind {
+            TokenKind::Byte => {
+                let text = self.text;
+                unescape_byte_str(&text[2..text.len() - 1]).first().copied()
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tokenize, Lexer};
+
+    fn single(src: &str) -> Token<'_> {
+        let tokens = tokenize(src);
+        assert_eq!(tokens.len(), 2, "{:?}", tokens);
+        assert_eq!(tokens[0].text, src);
+        tokens[0]
+    }
+
+    fn error(src: &str) -> (LexErrorKind, String) {
+        let err = Lexer::new(src)
+            .find_map(Result::err)
+            .expect("expected a lex error");
+        (err.kind, src[err.span.range()].to_string())
+    }
+
+    #[test]
+    fn strings() {
+        let t = single(r#""hello""#);
+        assert_eq!(t.kind, TokenKind::Str);
+        assert!(matches!(t.str_value(), Some(Cow::Borrowed("hello"))));
+
+        let t = single(r#""a\n\x7f\u{1F600}\"\\""#);
+        assert_eq!(t.str_value().unwrap(), "a\n\x7f\u{1F600}\"\\");
+
+        let t = single("\"line \\\n    continued\"");
+        assert_eq!(t.str_value().unwrap(), "line continued");
+
+        let t = single(r#""ユリ""#);
+        assert_eq!(t.str_value().unwrap(), "ユリ");
+    }
+
+    #[test]
+    fn byte_strings() {
+        let t = single(r#"b"GET /\r\n\xff""#);
+        assert_eq!(t.kind, TokenKind::ByteStr);
+        assert_eq!(&*t.byte_str_value().unwrap(), b"GET /\r\n\xff");
+        assert!(matches!(
+            single(r#"b"plain""#).byte_str_value(),
+            Some(Cow::Borrowed(b"plain"))
+        ));
+    }
+
+    #[test]
+    fn raw_strings() {
+        let t = single(r###"r#"a "quoted" \n"#"###);
+        assert_eq!(t.kind, TokenKind::RawStr { hashes: 1 });
+        assert_eq!(t.str_value().unwrap(), r#"a "quoted" \n"#);
+
+        let t = single(r#"r"""#);
+        assert_eq!(t.kind, TokenKind::RawStr { hashes: 0 });
+        assert_eq!(t.str_value().unwrap(), "");
+
+        let t = single(r###"br##"x"#y"##"###);
+        assert_eq!(t.kind, TokenKind::RawByteStr { hashes: 2 });
+        assert_eq!(&*t.byte_str_value().unwrap(), b"x\"#y");
+    }
+
+    #[test]
+    fn chars() {
+        assert_eq!(single("'a'").char_value(), Some('a'));
+        assert_eq!(single("'ユ'").char_value(), Some('ユ'));
+        assert_eq!(single(r"'\''").char_value(), Some('\''));
+        assert_eq!(single(r"'\u{3042}'").char_value(), Some('あ'));
+        assert_eq!(single("b'a'").byte_value(), Some(b'a'));
+        assert_eq!(single(r"b'\xff'").byte_value(), Some(0xff));
+    }
+
+    #[test]
+    fn errors() {
+        use LexErrorKind::*;
+        assert_eq!(error(r#""abc"#), (UnterminatedLiteral, "\"".into()));
+        assert_eq!(error(r#""\q""#), (InvalidEscape, r"\q".into()));
+        assert_eq!(error(r#""\x80""#), (OutOfRangeEscape, r"\x80".into()));
+        assert_eq!(error(r#""\x7""#), (InvalidEscape, r"\x7".into()));
+        assert_eq!(error(r#""\u{110000}""#).0, OutOfRangeEscape);
+        assert_eq!(error(r#""\u{}""#).0, InvalidEscape);
+        assert_eq!(error(r#"b"\u{41}""#).0, InvalidEscape);
+        assert_eq!(error(r#"b"ユ""#), (NonAsciiByte, "ユ".into()));
+        assert_eq!(error(r##"r#"abc"##), (UnterminatedLiteral, "r#\"".into()));
+        assert_eq!(error("''"), (EmptyChar, "''".into()));
+        assert_eq!(error("'ab'"), (OverlongChar, "'ab'".into()));
+        assert_eq!(error(r"'\n"), (UnterminatedLiteral, "'".into()));
+        assert_eq!(error("'\t'").0, UnescapedChar);
+    }
+
+    #[test]
+    fn r_and_b_are_still_identifiers() {
+        let tokens = tokenize("r b br rb");
+        assert!(tokens[..4].iter().all(|t| t.kind == TokenKind::Ident));
+    }
+}
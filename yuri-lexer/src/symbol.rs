@@ -0,0 +1,233 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::{Keyword, Token, TokenKind};
+
+/// An interned string. Two symbols from the same interner are equal exactly
+/// when their strings are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+
+    /// The keyword this symbol spells, if any.
+    pub fn keyword(self) -> Option<Keyword> {
+        Keyword::ALL.get(self.0 as usize).copied()
+    }
+}
+
+impl Keyword {
+    /// The symbol of this keyword, which every interner has pre-interned.
+    pub fn symbol(self) -> Symbol {
+        Symbol(self as u32)
+    }
+}
+
+/// Maps strings to `Symbol`s and back.
+#[derive(Debug)]
+pub struct Interner {
+    symbols: HashMap<Arc<str>, Symbol>,
+    strings: Vec<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        let mut interner = Interner {
+            symbols: HashMap::new(),
+            strings: Vec::new(),
+        };
+        for keyword in Keyword::ALL {
+            interner.intern(keyword.as_str());
+        }
+        interner
+    }
+
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(s) {
+            return symbol;
+        }
+        let symbol = Symbol(self.strings.len() as u32);
+        let s: Arc<str> = s.into();
+        self.strings.push(s.clone());
+        self.symbols.insert(s, symbol);
+        symbol
+    }
+
+    /// The symbol of `s` if it has been interned.
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        self.symbols.get(s).copied()
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `symbol` comes from a different interner.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.strings[symbol.0 as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+impl Default for Interner {
+    fn default() -> Self {
+        Interner::new()
+    }
+}
+
+/// An interner that can be shared between threads, e.g. to parse several
+/// files in parallel.
+#[derive(Debug, Default)]
+pub struct SyncInterner {
+    inner: RwLock<Interner>,
+}
+
+impl SyncInterner {
+    pub fn new() -> Self {
+        SyncInterner::default()
+    }
+
+    pub fn intern(&self, s: &str) -> Symbol {
+        if let Some(symbol) = self.get(s) {
+            return symbol;
+        }
+        self.inner.write().unwrap().intern(s)
+    }
+
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        self.inner.read().unwrap().get(s)
+    }
+
+    pub fn resolve(&self, symbol: Symbol) -> Arc<str> {
+        self.inner.read().unwrap().strings[symbol.0 as usize].clone()
+    }
+
+    /// Stops sharing the interner, e.g. once parallel parsing is done.
+    pub fn into_inner(self) -> Interner {
+        self.inner.into_inner().unwrap()
+    }
+}
+
+/// Something the lexer can intern symbols into while it is shared.
+pub trait Intern {
+    fn intern(&self, s: &str) -> Symbol;
+}
+
+impl Intern for RefCell<Interner> {
+    fn intern(&self, s: &str) -> Symbol {
+        self.borrow_mut().intern(s)
+    }
+}
+
+impl Intern for SyncInterner {
+    fn intern(&self, s: &str) -> Symbol {
+        SyncInterner::intern(self, s)
+    }
+}
+
+impl TokenKind {
+    /// Whether tokens of this kind carry a symbol when lexed with an
+    /// interner.
+    pub fn has_symbol(self) -> bool {
+        matches!(
+            self,
+            TokenKind::Ident
+                | TokenKind::Lifetime
+                | TokenKind::Int { .. }
+                | TokenKind::Float { .. }
+                | TokenKind::Duration(_)
+                | TokenKind::Size(_)
+                | TokenKind::Str
+                | TokenKind::ByteStr
+                | TokenKind::RawStr { .. }
+                | TokenKind::RawByteStr { .. }
+                | TokenKind::Char
+                | TokenKind::Byte
+        )
+    }
+}
+
+impl Token<'_> {
+    /// The string a token's symbol stands for: the NFC-normalized name of an
+    /// identifier or lifetime, or the text of a literal.
+    pub(crate) fn symbol_str(&self) -> Option<std::borrow::Cow<'_, str>> {
+        match self.kind {
+            TokenKind::Ident => self.normalized_name(),
+            TokenKind::Lifetime => Some(crate::normalize_ident(self.text)),
+            kind if kind.has_symbol() => Some(self.text.into()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Lexer;
+
+    #[test]
+    fn keywords_are_pre_interned() {
+        let mut interner = Interner::new();
+        for &keyword in Keyword::ALL {
+            assert_eq!(interner.intern(keyword.as_str()), keyword.symbol());
+            assert_eq!(keyword.symbol().keyword(), Some(keyword));
+        }
+        let foo = interner.intern("foo");
+        assert_eq!(foo.keyword(), None);
+        assert_eq!(interner.intern("foo"), foo);
+        assert_eq!(interner.resolve(foo), "foo");
+    }
+
+    #[test]
+    fn lexer_interns_idents_and_literals() {
+        let interner = RefCell::new(Interner::new());
+        let src = "let caf\u{e9} = cafe\u{301} + r#match + \"s\" + 1 + 1;";
+        let tokens: Vec<_> = Lexer::new(src)
+            .with_interner(&interner)
+            .map(Result::unwrap)
+            .collect();
+        let symbols: Vec<_> = tokens.iter().map(|t| t.symbol).collect();
+        assert_eq!(symbols[0], None);
+        // Canonically equivalent spellings share a symbol.
+        assert_eq!(symbols[1], symbols[3]);
+        assert_eq!(symbols[5], Some(Keyword::Match.symbol()));
+        assert_eq!(symbols[9], symbols[11]);
+        assert_eq!(interner.borrow().resolve(symbols[7].unwrap()), "\"s\"");
+    }
+
+    #[test]
+    fn sync_interner() {
+        let interner = SyncInterner::new();
+        let srcs = ["fn a b", "fn b c", "fn c a"];
+        let symbols: Vec<Vec<Symbol>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = srcs
+                .iter()
+                .map(|src| {
+                    let interner = &interner;
+                    scope.spawn(move || {
+                        Lexer::new(src)
+                            .with_interner(interner)
+                            .filter_map(|t| t.unwrap().symbol)
+                            .collect()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(symbols[0][1], symbols[1][0]);
+        assert_eq!(symbols[1][1], symbols[2][0]);
+        assert_eq!(symbols[2][1], symbols[0][0]);
+        let interner = interner.into_inner();
+        assert_eq!(interner.resolve(symbols[0][0]), "a");
+        assert_eq!(interner.len(), Keyword::ALL.len() + 3);
+    }
+}
@@ -0,0 +1,1461 @@
+//! The abstract syntax tree of a source file, as typed views of the nodes of
+//! its concrete syntax tree.
+//!
+//! Each view wraps a `SyntaxNode` of the right kind and nothing else, so
+//! casting to one is just a check of the kind, and the tokens, trivia
+//! included, stay in the tree underneath. The accessors return `None` where
+//! a syntax error left a part out.
+//!
+//! Every node has a `NodeId`, unique within its file, so that later passes
+//! can attach information to nodes in side tables instead of in the tree,
+//! and the `Span` of the source it was parsed from.
+
+use yuri_lexer::{Base, FloatTy, IntTy, Keyword, SizeUnit, Span, Symbol, TimeUnit, TokenKind};
+
+use crate::expr::lit_kind;
+pub use crate::syntax::NodeId;
+use crate::syntax::{NodeKind, SyntaxElement, SyntaxNode, SyntaxToken};
+
+/// A typed view of a syntax node.
+pub trait AstNode: Sized {
+    fn can_cast(kind: NodeKind) -> bool;
+
+    fn cast(node: SyntaxNode) -> Option<Self>;
+
+    fn syntax(&self) -> &SyntaxNode;
+
+    fn id(&self) -> NodeId {
+        self.syntax().id()
+    }
+
+    fn span(&self) -> Span {
+        self.syntax().span()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ident {
+    pub name: Symbol,
+    pub span: Span,
+}
+
+/// The identifier of `node`, a `Name` or `NameRef`, which is a keyword for
+/// path segments such as `self` and a number for tuple fields.
+fn ident(node: &SyntaxNode) -> Ident {
+    let token = node.tokens().next().expect("a name has a token");
+    let name = match token.kind() {
+        TokenKind::Keyword(keyword) => keyword.symbol(),
+        _ => token.symbol().expect("a name has a symbol"),
+    };
+    Ident {
+        name,
+        span: token.span(),
+    }
+}
+
+fn child<N: AstNode>(parent: &SyntaxNode) -> Option<N> {
+    parent.children().find_map(N::cast)
+}
+
+fn children<N: AstNode>(parent: &SyntaxNode) -> impl Iterator<Item = N> {
+    parent.children().filter_map(N::cast)
+}
+
+fn token(parent: &SyntaxNode, kind: TokenKind) -> Option<SyntaxToken> {
+    parent.children_with_tokens().find_map(|child| match child {
+        SyntaxElement::Token(token) if token.kind() == kind => Some(token),
+        _ => None,
+    })
+}
+
+fn has_token(parent: &SyntaxNode, kind: TokenKind) -> bool {
+    token(parent, kind).is_some()
+}
+
+/// The last child of `parent` before its first token of one of the `ops`
+/// kinds, and the first child after it, as the operands of a binary
+/// operator.
+fn around<N: AstNode>(parent: &SyntaxNode, ops: &[TokenKind]) -> (Option<N>, Option<N>) {
+    let mut elements = parent.children_with_tokens();
+    let mut before = None;
+    for element in &mut elements {
+        match element {
+            SyntaxElement::Token(token) if ops.contains(&token.kind()) => break,
+            SyntaxElement::Node(node) => before = N::cast(node).or(before),
+            SyntaxElement::Token(_) => {}
+        }
+    }
+    let after = elements.find_map(|element| match element {
+        SyntaxElement::Node(node) => N::cast(node),
+        SyntaxElement::Token(_) => None,
+    });
+    (before, after)
+}
+
+/// The first child of `parent` after its token of `kind`.
+fn child_after<N: AstNode>(parent: &SyntaxNode, kind: TokenKind) -> Option<N> {
+    if !has_token(parent, kind) {
+        return None;
+    }
+    around(parent, &[kind]).1
+}
+
+macro_rules! nodes {
+    ($($(#[$attr:meta])* $name:ident,)*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Clone, Debug, PartialEq, Eq)]
+            #[repr(transparent)]
+            pub struct $name(SyntaxNode);
+
+            impl AstNode for $name {
+                fn can_cast(kind: NodeKind) -> bool {
+                    kind == NodeKind::$name
+                }
+
+                fn cast(node: SyntaxNode) -> Option<Self> {
+                    if Self::can_cast(node.kind()) {
+                        Some($name(node))
+                    } else {
+                        None
+                    }
+                }
+
+                fn syntax(&self) -> &SyntaxNode {
+                    &self.0
+                }
+            }
+        )*
+    };
+}
+
+/// Views of every node of a few related kinds, such as all expressions, as
+/// an enum of the views of each kind.
+macro_rules! node_enums {
+    ($($(#[$attr:meta])* $name:ident { $($variant:ident($node:ident),)* })*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Clone, Debug, PartialEq, Eq)]
+            pub enum $name {
+                $($variant($node),)*
+            }
+
+            impl AstNode for $name {
+                fn can_cast(kind: NodeKind) -> bool {
+                    $($node::can_cast(kind))||*
+                }
+
+                fn cast(node: SyntaxNode) -> Option<Self> {
+                    $(
+                        if $node::can_cast(node.kind()) {
+                            return Some($name::$variant($node(node)));
+                        }
+                    )*
+                    None
+                }
+
+                fn syntax(&self) -> &SyntaxNode {
+                    match self {
+                        $($name::$variant(node) => node.syntax(),)*
+                    }
+                }
+            }
+        )*
+    };
+}
+
+nodes! {
+    SourceFile,
+    Fn,
+    Struct,
+    Enum,
+    Impl,
+    Trait,
+    Use,
+    Mod,
+    GenericParam,
+    ParamList,
+    SelfParam,
+    Param,
+    FieldDef,
+    Variant,
+    UseTree,
+    Name,
+    NameRef,
+    Path,
+    GenericArgs,
+    PathTy,
+    RefTy,
+    ArrayTy,
+    SliceTy,
+    TupleTy,
+    FnTy,
+    WildPat,
+    RestPat,
+    BindingPat,
+    LitPat,
+    RangePat,
+    PathPat,
+    TupleStructPat,
+    StructPat,
+    PatField,
+    TuplePat,
+    SlicePat,
+    OrPat,
+    Block,
+    LetStmt,
+    ExprStmt,
+    LitExpr,
+    PathExpr,
+    ParenExpr,
+    TupleExpr,
+    ArrayExpr,
+    RepeatExpr,
+    StructExpr,
+    ExprField,
+    UnaryExpr,
+    BinaryExpr,
+    RefExpr,
+    AssignExpr,
+    CastExpr,
+    CallExpr,
+    MethodCallExpr,
+    FieldExpr,
+    IndexExpr,
+    TryExpr,
+    RangeExpr,
+    IfExpr,
+    LoopExpr,
+    WhileExpr,
+    MatchExpr,
+    Arm,
+    MatchGuard,
+    LetExpr,
+    BreakExpr,
+    ContinueExpr,
+    ReturnExpr,
+    YieldExpr,
+    /// Source skipped after a syntax error, or text that did not lex.
+    Error,
+}
+
+node_enums! {
+    /// An item, or an `Error` in place of one.
+    Item {
+        Fn(Fn),
+        Struct(Struct),
+        Enum(Enum),
+        Impl(Impl),
+        Trait(Trait),
+        Use(Use),
+        Mod(Mod),
+        Error(Error),
+    }
+
+    Ty {
+        Path(PathTy),
+        Ref(RefTy),
+        Array(ArrayTy),
+        Slice(SliceTy),
+        Tuple(TupleTy),
+        Fn(FnTy),
+    }
+
+    Pat {
+        Wild(WildPat),
+        Rest(RestPat),
+        Binding(BindingPat),
+        Lit(LitPat),
+        Range(RangePat),
+        Path(PathPat),
+        TupleStruct(TupleStructPat),
+        Struct(StructPat),
+        Tuple(TuplePat),
+        Slice(SlicePat),
+        Or(OrPat),
+    }
+
+    /// An expression, including a block and an `Error` in place of one.
+    Expr {
+        Block(Block),
+        Lit(LitExpr),
+        Path(PathExpr),
+        Paren(ParenExpr),
+        Tuple(TupleExpr),
+        Array(ArrayExpr),
+        Repeat(RepeatExpr),
+        Struct(StructExpr),
+        Unary(UnaryExpr),
+        Binary(BinaryExpr),
+        Ref(RefExpr),
+        Assign(AssignExpr),
+        Cast(CastExpr),
+        Call(CallExpr),
+        MethodCall(MethodCallExpr),
+        Field(FieldExpr),
+        Index(IndexExpr),
+        Try(TryExpr),
+        Range(RangeExpr),
+        If(IfExpr),
+        Loop(LoopExpr),
+        While(WhileExpr),
+        Match(MatchExpr),
+        Let(LetExpr),
+        Break(BreakExpr),
+        Continue(ContinueExpr),
+        Return(ReturnExpr),
+        Yield(YieldExpr),
+        Error(Error),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Visibility {
+    Private,
+    /// `pub`
+    Public,
+    /// `pub(crate)`
+    Crate,
+}
+
+fn visibility(node: &SyntaxNode) -> Visibility {
+    if !has_token(node, TokenKind::Keyword(Keyword::Pub)) {
+        Visibility::Private
+    } else if has_token(node, TokenKind::Keyword(Keyword::Crate)) {
+        Visibility::Crate
+    } else {
+        Visibility::Public
+    }
+}
+
+/// How the fields of a struct or enum variant are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldsKind {
+    Unit,
+    /// `(A, B)`, which `tuple_fields` lists.
+    Tuple,
+    /// `{ a: A, b: B }`, which `named_fields` lists.
+    Named,
+}
+
+fn fields_kind(node: &SyntaxNode) -> FieldsKind {
+    if has_token(node, TokenKind::BraceOpen) {
+        FieldsKind::Named
+    } else if has_token(node, TokenKind::ParenOpen) {
+        FieldsKind::Tuple
+    } else {
+        FieldsKind::Unit
+    }
+}
+
+impl SourceFile {
+    pub fn items(&self) -> impl Iterator<Item = Item> {
+        children(&self.0)
+    }
+}
+
+impl Item {
+    pub fn visibility(&self) -> Visibility {
+        visibility(self.syntax())
+    }
+
+    /// The name being defined. `impl` and `use` items have none.
+    pub fn name(&self) -> Option<Name> {
+        child(self.syntax())
+    }
+}
+
+impl Fn {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn generic_params(&self) -> impl Iterator<Item = GenericParam> {
+        children(&self.0)
+    }
+
+    /// The parameter lists, one per group of parentheses.
+    pub fn param_lists(&self) -> impl Iterator<Item = ParamList> {
+        children(&self.0)
+    }
+
+    pub fn ret_ty(&self) -> Option<Ty> {
+        child(&self.0)
+    }
+
+    /// The body, or `None` for a declaration ending in `;`.
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+/// What a generic parameter declares.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GenericParamKind {
+    /// `T: Bound`
+    Type,
+    /// `'a: 'b`
+    Lifetime,
+    /// `const N: usize`
+    Const,
+}
+
+impl GenericParam {
+    pub fn kind(&self) -> GenericParamKind {
+        if has_token(&self.0, TokenKind::Keyword(Keyword::Const)) {
+            GenericParamKind::Const
+        } else if self
+            .name()
+            .and_then(|name| name.ident_token())
+            .map(|t| t.kind())
+            == Some(TokenKind::Lifetime)
+        {
+            GenericParamKind::Lifetime
+        } else {
+            GenericParamKind::Type
+        }
+    }
+
+    /// The name of the parameter, which for a lifetime includes its `'`.
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    /// The trait bounds of a type parameter.
+    pub fn bounds(&self) -> impl Iterator<Item = Path> {
+        children(&self.0)
+    }
+
+    /// The lifetimes that the parameter must outlive, as `'b` in `'a: 'b`
+    /// or `T: 'b`.
+    pub fn lifetime_bounds(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(|child| match child {
+                SyntaxElement::Token(token) if token.kind() == TokenKind::Lifetime => Some(token),
+                _ => None,
+            })
+    }
+
+    /// The type of a const parameter.
+    pub fn ty(&self) -> Option<Ty> {
+        child(&self.0)
+    }
+}
+
+impl ParamList {
+    pub fn self_param(&self) -> Option<SelfParam> {
+        child(&self.0)
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = Param> {
+        children(&self.0)
+    }
+}
+
+impl SelfParam {
+    /// Whether this is `&self` or `&mut self`.
+    pub fn is_ref(&self) -> bool {
+        has_token(&self.0, TokenKind::Amp)
+    }
+
+    pub fn is_mut(&self) -> bool {
+        has_token(&self.0, TokenKind::Keyword(Keyword::Mut))
+    }
+}
+
+impl Param {
+    pub fn pat(&self) -> Option<Pat> {
+        child(&self.0)
+    }
+
+    pub fn ty(&self) -> Option<Ty> {
+        child(&self.0)
+    }
+}
+
+impl Struct {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn generic_params(&self) -> impl Iterator<Item = GenericParam> {
+        children(&self.0)
+    }
+
+    pub fn fields_kind(&self) -> FieldsKind {
+        fields_kind(&self.0)
+    }
+
+    pub fn named_fields(&self) -> impl Iterator<Item = FieldDef> {
+        children(&self.0)
+    }
+
+    pub fn tuple_fields(&self) -> impl Iterator<Item = Ty> {
+        children(&self.0)
+    }
+}
+
+impl FieldDef {
+    pub fn visibility(&self) -> Visibility {
+        visibility(&self.0)
+    }
+
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn ty(&self) -> Option<Ty> {
+        child(&self.0)
+    }
+}
+
+impl Enum {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn generic_params(&self) -> impl Iterator<Item = GenericParam> {
+        children(&self.0)
+    }
+
+    pub fn variants(&self) -> impl Iterator<Item = Variant> {
+        children(&self.0)
+    }
+}
+
+impl Variant {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn fields_kind(&self) -> FieldsKind {
+        fields_kind(&self.0)
+    }
+
+    pub fn named_fields(&self) -> impl Iterator<Item = FieldDef> {
+        children(&self.0)
+    }
+
+    pub fn tuple_fields(&self) -> impl Iterator<Item = Ty> {
+        children(&self.0)
+    }
+}
+
+impl Impl {
+    pub fn generic_params(&self) -> impl Iterator<Item = GenericParam> {
+        children(&self.0)
+    }
+
+    /// The trait in `impl Trait for Ty`.
+    pub fn trait_ty(&self) -> Option<PathTy> {
+        if has_token(&self.0, TokenKind::Keyword(Keyword::For)) {
+            child(&self.0)
+        } else {
+            None
+        }
+    }
+
+    pub fn self_ty(&self) -> Option<Ty> {
+        if has_token(&self.0, TokenKind::Keyword(Keyword::For)) {
+            child_after(&self.0, TokenKind::Keyword(Keyword::For))
+        } else {
+            child(&self.0)
+        }
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = Item> {
+        children(&self.0)
+    }
+}
+
+impl Trait {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn generic_params(&self) -> impl Iterator<Item = GenericParam> {
+        children(&self.0)
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = Item> {
+        children(&self.0)
+    }
+}
+
+impl Use {
+    pub fn use_tree(&self) -> Option<UseTree> {
+        child(&self.0)
+    }
+}
+
+impl UseTree {
+    /// The path up to the `*`, the braces or the last name.
+    pub fn segments(&self) -> impl Iterator<Item = NameRef> {
+        children(&self.0)
+    }
+
+    pub fn is_glob(&self) -> bool {
+        has_token(&self.0, TokenKind::Star)
+    }
+
+    /// The trees within the braces of `a::{b, c}`.
+    pub fn use_trees(&self) -> impl Iterator<Item = UseTree> {
+        children(&self.0)
+    }
+
+    /// The new name in `a as b`.
+    pub fn rename(&self) -> Option<Name> {
+        child(&self.0)
+    }
+}
+
+impl Mod {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    /// The items of an inline module. Empty for `mod foo;`.
+    pub fn items(&self) -> impl Iterator<Item = Item> {
+        children(&self.0)
+    }
+}
+
+impl Name {
+    pub fn ident(&self) -> Ident {
+        ident(&self.0)
+    }
+
+    pub fn ident_token(&self) -> Option<SyntaxToken> {
+        self.0.tokens().next()
+    }
+
+    pub fn text(&self) -> String {
+        self.0.text()
+    }
+}
+
+impl NameRef {
+    /// The identifier, or the keyword of a path segment such as `self` or
+    /// the number of a tuple field.
+    pub fn ident(&self) -> Ident {
+        ident(&self.0)
+    }
+
+    pub fn ident_token(&self) -> Option<SyntaxToken> {
+        self.0.tokens().next()
+    }
+
+    pub fn text(&self) -> String {
+        self.0.text()
+    }
+}
+
+/// A segment of a path, with its generic arguments if it has any.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathSegment {
+    pub name: NameRef,
+    pub generic_args: Option<GenericArgs>,
+}
+
+impl Path {
+    pub fn segments(&self) -> impl Iterator<Item = PathSegment> {
+        let mut segments: Vec<PathSegment> = Vec::new();
+        for node in self.0.children() {
+            if let Some(name) = NameRef::cast(node.clone()) {
+                segments.push(PathSegment {
+                    name,
+                    generic_args: None,
+                });
+            } else if let (Some(args), Some(last)) = (GenericArgs::cast(node), segments.last_mut())
+            {
+                last.generic_args = Some(args);
+            }
+        }
+        segments.into_iter()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum GenericArg {
+    Type(Ty),
+    /// A literal or a block.
+    Const(Expr),
+}
+
+impl GenericArgs {
+    pub fn args(&self) -> impl Iterator<Item = GenericArg> {
+        self.0
+            .children()
+            .filter_map(|node| match Ty::cast(node.clone()) {
+                Some(ty) => Some(GenericArg::Type(ty)),
+                None => Expr::cast(node).map(GenericArg::Const),
+            })
+    }
+}
+
+impl PathTy {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+}
+
+impl RefTy {
+    pub fn lifetime(&self) -> Option<SyntaxToken> {
+        token(&self.0, TokenKind::Lifetime)
+    }
+
+    pub fn is_mut(&self) -> bool {
+        has_token(&self.0, TokenKind::Keyword(Keyword::Mut))
+    }
+
+    pub fn ty(&self) -> Option<Ty> {
+        child(&self.0)
+    }
+}
+
+impl ArrayTy {
+    pub fn elem(&self) -> Option<Ty> {
+        child(&self.0)
+    }
+
+    pub fn len(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl SliceTy {
+    pub fn elem(&self) -> Option<Ty> {
+        child(&self.0)
+    }
+}
+
+impl TupleTy {
+    pub fn fields(&self) -> impl Iterator<Item = Ty> {
+        children(&self.0)
+    }
+}
+
+impl FnTy {
+    /// The parameter types, one list per group of parentheses.
+    pub fn param_lists(&self) -> Vec<Vec<Ty>> {
+        let mut lists = Vec::new();
+        let mut in_parens = false;
+        for element in self.0.children_with_tokens() {
+            match element {
+                SyntaxElement::Token(token) => match token.kind() {
+                    TokenKind::ParenOpen => {
+                        lists.push(Vec::new());
+                        in_parens = true;
+                    }
+                    TokenKind::ParenClose => in_parens = false,
+                    _ => {}
+                },
+                SyntaxElement::Node(node) if in_parens => {
+                    lists.last_mut().unwrap().extend(Ty::cast(node));
+                }
+                SyntaxElement::Node(_) => {}
+            }
+        }
+        lists
+    }
+
+    pub fn ret_ty(&self) -> Option<Ty> {
+        child_after(&self.0, TokenKind::Colon)
+    }
+}
+
+impl BindingPat {
+    pub fn is_ref(&self) -> bool {
+        has_token(&self.0, TokenKind::Keyword(Keyword::Ref))
+    }
+
+    pub fn is_mut(&self) -> bool {
+        has_token(&self.0, TokenKind::Keyword(Keyword::Mut))
+    }
+
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    /// The pattern after `@`.
+    pub fn sub_pat(&self) -> Option<Pat> {
+        child(&self.0)
+    }
+}
+
+impl LitPat {
+    /// The literal, or a negated number literal such as `-1`.
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+const RANGE_OPS: &[TokenKind] = &[TokenKind::DotDot, TokenKind::DotDotEqual];
+
+impl RangePat {
+    pub fn start(&self) -> Option<Expr> {
+        around(&self.0, RANGE_OPS).0
+    }
+
+    pub fn end(&self) -> Option<Expr> {
+        around(&self.0, RANGE_OPS).1
+    }
+
+    pub fn is_inclusive(&self) -> bool {
+        has_token(&self.0, TokenKind::DotDotEqual)
+    }
+}
+
+impl PathPat {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+}
+
+impl TupleStructPat {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = Pat> {
+        children(&self.0)
+    }
+}
+
+impl StructPat {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = PatField> {
+        children(&self.0)
+    }
+
+    /// Whether the fields end in `..`.
+    pub fn has_rest(&self) -> bool {
+        has_token(&self.0, TokenKind::DotDot)
+    }
+}
+
+impl PatField {
+    /// The field name, or `None` in `S { ref x }`, where the binding names
+    /// the field.
+    pub fn name_ref(&self) -> Option<NameRef> {
+        child(&self.0)
+    }
+
+    pub fn pat(&self) -> Option<Pat> {
+        child(&self.0)
+    }
+}
+
+impl TuplePat {
+    pub fn fields(&self) -> impl Iterator<Item = Pat> {
+        children(&self.0)
+    }
+}
+
+impl SlicePat {
+    pub fn pats(&self) -> impl Iterator<Item = Pat> {
+        children(&self.0)
+    }
+}
+
+impl OrPat {
+    pub fn pats(&self) -> impl Iterator<Item = Pat> {
+        children(&self.0)
+    }
+}
+
+/// A statement in a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Stmt {
+    Let(LetStmt),
+    Expr(ExprStmt),
+    Item(Item),
+}
+
+impl Stmt {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        match node.kind() {
+            NodeKind::LetStmt => Some(Stmt::Let(LetStmt(node))),
+            NodeKind::ExprStmt => Some(Stmt::Expr(ExprStmt(node))),
+            kind if kind.is_item() => Item::cast(node).map(Stmt::Item),
+            _ => None,
+        }
+    }
+}
+
+impl Block {
+    pub fn stmts(&self) -> impl Iterator<Item = Stmt> {
+        self.0.children().filter_map(Stmt::cast)
+    }
+}
+
+impl LetStmt {
+    pub fn pat(&self) -> Option<Pat> {
+        child(&self.0)
+    }
+
+    pub fn ty(&self) -> Option<Ty> {
+        child(&self.0)
+    }
+
+    pub fn init(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl ExprStmt {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn has_semicolon(&self) -> bool {
+        has_token(&self.0, TokenKind::Semicolon)
+    }
+}
+
+impl Expr {
+    pub fn kind(&self) -> NodeKind {
+        self.syntax().kind()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LitKind {
+    Bool(bool),
+    Int { base: Base, suffix: Option<IntTy> },
+    Float { suffix: Option<FloatTy> },
+    Duration(TimeUnit),
+    Size(SizeUnit),
+    Str,
+    ByteStr,
+    RawStr { hashes: u8 },
+    RawByteStr { hashes: u8 },
+    Char,
+    Byte,
+}
+
+impl LitExpr {
+    pub fn token(&self) -> SyntaxToken {
+        self.0.tokens().next().expect("a literal has a token")
+    }
+
+    pub fn kind(&self) -> LitKind {
+        lit_kind(self.token().kind()).expect("not a literal")
+    }
+
+    /// The literal's text, or the keyword for `true` and `false`.
+    pub fn symbol(&self) -> Symbol {
+        let token = self.token();
+        match token.kind() {
+            TokenKind::Keyword(keyword) => keyword.symbol(),
+            _ => token.symbol().expect("a literal has a symbol"),
+        }
+    }
+}
+
+impl PathExpr {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+}
+
+impl ParenExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl TupleExpr {
+    pub fn fields(&self) -> impl Iterator<Item = Expr> {
+        children(&self.0)
+    }
+}
+
+impl ArrayExpr {
+    pub fn elems(&self) -> impl Iterator<Item = Expr> {
+        children(&self.0)
+    }
+}
+
+impl RepeatExpr {
+    pub fn elem(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn len(&self) -> Option<Expr> {
+        child_after(&self.0, TokenKind::Semicolon)
+    }
+}
+
+impl StructExpr {
+    pub fn path(&self) -> Option<Path> {
+        child(&self.0)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = ExprField> {
+        children(&self.0)
+    }
+}
+
+impl ExprField {
+    pub fn name_ref(&self) -> Option<NameRef> {
+        child(&self.0)
+    }
+
+    /// The value, or `None` in `S { x }`, where the name is the value.
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum UnOp {
+    /// `-`
+    Neg,
+    /// `!`
+    Not,
+    /// `*`
+    Deref,
+}
+
+impl UnOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UnOp::Neg => "-",
+            UnOp::Not => "!",
+            UnOp::Deref => "*",
+        }
+    }
+}
+
+impl UnaryExpr {
+    pub fn op(&self) -> Option<UnOp> {
+        self.0.children_with_tokens().find_map(|child| match child {
+            SyntaxElement::Token(token) => match token.kind() {
+                TokenKind::Minus => Some(UnOp::Neg),
+                TokenKind::Bang => Some(UnOp::Not),
+                TokenKind::Star => Some(UnOp::Deref),
+                _ => None,
+            },
+            SyntaxElement::Node(_) => None,
+        })
+    }
+
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    And,
+    Or,
+    BitXor,
+    BitAnd,
+    BitOr,
+    Shl,
+    Shr,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl BinOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+            BinOp::BitXor => "^",
+            BinOp::BitAnd => "&",
+            BinOp::BitOr => "|",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+        }
+    }
+
+    fn from_token(kind: TokenKind) -> Option<BinOp> {
+        Some(match kind {
+            TokenKind::Plus => BinOp::Add,
+            TokenKind::Minus => BinOp::Sub,
+            TokenKind::Star => BinOp::Mul,
+            TokenKind::Slash => BinOp::Div,
+            TokenKind::Percent => BinOp::Rem,
+            TokenKind::AmpAmp => BinOp::And,
+            TokenKind::PipePipe => BinOp::Or,
+            TokenKind::Caret => BinOp::BitXor,
+            TokenKind::Amp => BinOp::BitAnd,
+            TokenKind::Pipe => BinOp::BitOr,
+            TokenKind::LessLess => BinOp::Shl,
+            TokenKind::GreaterGreater => BinOp::Shr,
+            TokenKind::EqualEqual => BinOp::Eq,
+            TokenKind::BangEqual => BinOp::Ne,
+            TokenKind::Less => BinOp::Lt,
+            TokenKind::LessEqual => BinOp::Le,
+            TokenKind::Greater => BinOp::Gt,
+            TokenKind::GreaterEqual => BinOp::Ge,
+            _ => return None,
+        })
+    }
+
+    /// The operator of a compound assignment such as `+=`.
+    fn from_assign_token(kind: TokenKind) -> Option<BinOp> {
+        Some(match kind {
+            TokenKind::PlusEqual => BinOp::Add,
+            TokenKind::MinusEqual => BinOp::Sub,
+            TokenKind::StarEqual => BinOp::Mul,
+            TokenKind::SlashEqual => BinOp::Div,
+            TokenKind::PercentEqual => BinOp::Rem,
+            TokenKind::CaretEqual => BinOp::BitXor,
+            TokenKind::AmpEqual => BinOp::BitAnd,
+            TokenKind::PipeEqual => BinOp::BitOr,
+            TokenKind::LessLessEqual => BinOp::Shl,
+            TokenKind::GreaterGreaterEqual => BinOp::Shr,
+            _ => return None,
+        })
+    }
+}
+
+/// The first token of `node` itself that `f` maps to something.
+fn find_token<T>(node: &SyntaxNode, f: fn(TokenKind) -> Option<T>) -> Option<T> {
+    node.children_with_tokens().find_map(|child| match child {
+        SyntaxElement::Token(token) => f(token.kind()),
+        SyntaxElement::Node(_) => None,
+    })
+}
+
+impl BinaryExpr {
+    pub fn op(&self) -> Option<BinOp> {
+        find_token(&self.0, BinOp::from_token)
+    }
+
+    pub fn lhs(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn rhs(&self) -> Option<Expr> {
+        children(&self.0).nth(1)
+    }
+}
+
+impl RefExpr {
+    pub fn is_mut(&self) -> bool {
+        has_token(&self.0, TokenKind::Keyword(Keyword::Mut))
+    }
+
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl AssignExpr {
+    /// The operator of a compound assignment such as `+=`, or `None` for
+    /// `=`.
+    pub fn op(&self) -> Option<BinOp> {
+        find_token(&self.0, BinOp::from_assign_token)
+    }
+
+    pub fn lhs(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn rhs(&self) -> Option<Expr> {
+        children(&self.0).nth(1)
+    }
+}
+
+impl CastExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn ty(&self) -> Option<Ty> {
+        child(&self.0)
+    }
+}
+
+impl CallExpr {
+    pub fn callee(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn args(&self) -> impl Iterator<Item = Expr> {
+        children(&self.0).skip(1)
+    }
+}
+
+impl MethodCallExpr {
+    pub fn receiver(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn name_ref(&self) -> Option<NameRef> {
+        child(&self.0)
+    }
+
+    /// The generic arguments after `::`.
+    pub fn generic_args(&self) -> Option<GenericArgs> {
+        child(&self.0)
+    }
+
+    pub fn args(&self) -> impl Iterator<Item = Expr> {
+        children(&self.0).skip(1)
+    }
+}
+
+impl FieldExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    /// The field name, or the index of a tuple field.
+    pub fn name_ref(&self) -> Option<NameRef> {
+        child(&self.0)
+    }
+}
+
+impl IndexExpr {
+    pub fn base(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn index(&self) -> Option<Expr> {
+        children(&self.0).nth(1)
+    }
+}
+
+impl TryExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl RangeExpr {
+    pub fn start(&self) -> Option<Expr> {
+        around(&self.0, RANGE_OPS).0
+    }
+
+    pub fn end(&self) -> Option<Expr> {
+        around(&self.0, RANGE_OPS).1
+    }
+
+    pub fn is_inclusive(&self) -> bool {
+        has_token(&self.0, TokenKind::DotDotEqual)
+    }
+}
+
+impl IfExpr {
+    /// The condition, which may be a `let` expression.
+    pub fn cond(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn then_branch(&self) -> Option<Block> {
+        children::<Expr>(&self.0)
+            .nth(1)
+            .and_then(|expr| match expr {
+                Expr::Block(block) => Some(block),
+                _ => None,
+            })
+    }
+
+    /// The block or `if` expression after `else`.
+    pub fn else_branch(&self) -> Option<Expr> {
+        child_after(&self.0, TokenKind::Keyword(Keyword::Else))
+    }
+}
+
+impl LoopExpr {
+    pub fn body(&self) -> Option<Block> {
+        child(&self.0)
+    }
+}
+
+impl WhileExpr {
+    /// The condition, which may be a `let` expression.
+    pub fn cond(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn body(&self) -> Option<Block> {
+        children::<Expr>(&self.0)
+            .nth(1)
+            .and_then(|expr| match expr {
+                Expr::Block(block) => Some(block),
+                _ => None,
+            })
+    }
+}
+
+impl MatchExpr {
+    pub fn scrutinee(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+
+    pub fn arms(&self) -> impl Iterator<Item = Arm> {
+        children(&self.0)
+    }
+}
+
+impl Arm {
+    pub fn pat(&self) -> Option<Pat> {
+        child(&self.0)
+    }
+
+    pub fn guard(&self) -> Option<MatchGuard> {
+        child(&self.0)
+    }
+
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl MatchGuard {
+    pub fn cond(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl LetExpr {
+    pub fn pat(&self) -> Option<Pat> {
+        child(&self.0)
+    }
+
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl BreakExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+impl ReturnExpr {
+    pub fn expr(&self) -> Option<Expr> {
+        child(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use yuri_lexer::{FileId, Interner, Lexer};
+
+    use super::*;
+
+    #[test]
+    fn typed_views() {
+        let interner = RefCell::new(Interner::new());
+        let parse = crate::parse(
+            Lexer::with_file(
+                "
+pub fn copy (src: Fd) (dest: Fd) : Result {
+    let n = read(src);
+    write(dest, n)
+}
+
+enum Event { Read, Close }
+",
+                FileId(3),
+            ),
+            &interner,
+        );
+        let items: Vec<_> = parse.source_file().items().collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].visibility(), Visibility::Public);
+        assert_eq!(items[1].visibility(), Visibility::Private);
+
+        let copy = match &items[0] {
+            Item::Fn(copy) => copy,
+            _ => panic!("{:?}", items[0]),
+        };
+        assert_eq!(copy.name().unwrap().text(), "copy");
+        let name = copy.name().unwrap().ident();
+        assert_eq!(interner.borrow().resolve(name.name), "copy");
+        assert_eq!(name.span, Span::new(FileId(3), 8, 12));
+        assert_eq!(copy.span(), Span::new(FileId(3), 1, 88));
+        let params: Vec<_> = copy
+            .param_lists()
+            .flat_map(|list| list.params())
+            .map(|param| param.pat().unwrap().syntax().text())
+            .collect();
+        assert_eq!(params, ["src", "dest"]);
+        assert_eq!(copy.ret_ty().unwrap().syntax().text(), "Result");
+
+        let stmts: Vec<_> = copy.body().unwrap().stmts().collect();
+        match &stmts[..] {
+            [Stmt::Let(let_), Stmt::Expr(expr)] => {
+                assert_eq!(let_.init().unwrap().kind(), NodeKind::CallExpr);
+                assert!(!expr.has_semicolon());
+                // `n` is the same symbol where it is bound and used.
+                let bound = match let_.pat() {
+                    Some(Pat::Binding(pat)) => pat.name().unwrap().ident(),
+                    pat => panic!("{:?}", pat),
+                };
+                let used = expr
+                    .syntax()
+                    .descendants()
+                    .filter_map(NameRef::cast)
+                    .last()
+                    .unwrap()
+                    .ident();
+                assert_eq!(bound.name, used.name);
+                assert_ne!(bound.span, used.span);
+            }
+            _ => panic!("{:?}", stmts),
+        }
+
+        let variants: Vec<_> = match &items[1] {
+            Item::Enum(event) => event
+                .variants()
+                .map(|variant| variant.name().unwrap().text())
+                .collect(),
+            _ => panic!("{:?}", items[1]),
+        };
+        assert_eq!(variants, ["Read", "Close"]);
+        assert!(Struct::cast(items[1].syntax().clone()).is_none());
+    }
+
+    #[test]
+    fn node_ids_are_preorder_indices() {
+        let parse = crate::parse(
+            Lexer::new(
+                "fn f () { g(1) }
+struct S;",
+            ),
+            &RefCell::new(Interner::new()),
+        );
+        let root = parse.syntax();
+        let ids: Vec<_> = root.descendants().map(|node| node.id().as_u32()).collect();
+        let count = parse.green().node_count() as u32;
+        assert_eq!(ids, (0..count).collect::<Vec<_>>());
+        let items: Vec<_> = parse.source_file().items().map(|item| item.id()).collect();
+        assert_eq!(items[1].as_u32(), count - 2);
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt;
+
+use yuri_lexer::{Span, TokenKind};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// One of `expected` was expected instead of the token `found`, e.g.
+    /// "expected `,` or `)`, found identifier". `after` names what was just
+    /// parsed, if that makes the message clearer.
+    Expected {
+        expected: Vec<Expected>,
+        after: Option<&'static str>,
+        found: TokenKind,
+    },
+    /// `a < b < c`. Comparisons need parentheses to be combined.
+    ChainedComparison,
+    /// `Vec<u8>::new()`, which parses as comparisons. Generic arguments in
+    /// expressions are written `Vec::<u8>`.
+    MissingTurbofish,
+    /// `a..b..c`.
+    ChainedRange,
+    /// A float literal with an exponent or suffix used as a tuple index, e.g.
+    /// `t.1e3`.
+    InvalidTupleIndex,
+}
+
+/// What the parser was looking for when it failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Expected {
+    Token(TokenKind),
+    /// A syntactic category such as "expression" or "pattern".
+    Syntax(&'static str),
+}
+
+/// An error produced while parsing. `span` points at the offending token.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Span,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, span: Span) -> Self {
+        ParseError { kind, span }
+    }
+}
+
+/// How a token is referred to in diagnostics: its text in backquotes if all
+/// tokens of its kind are spelled the same, or else a description.
+pub(crate) fn describe(kind: TokenKind) -> String {
+    use TokenKind::*;
+    let text = match kind {
+        Ident => return "identifier".to_string(),
+        Keyword(keyword) => keyword.as_str(),
+        Underscore => "_",
+        DocComment(_) => return "doc comment".to_string(),
+        Whitespace | LineComment | BlockComment => return "comment".to_string(),
+        Error => return "invalid token".to_string(),
+        Int { .. } | Float { .. } | Duration(_) | Size(_) => return "number literal".to_string(),
+        Str | ByteStr | RawStr { .. } | RawByteStr { .. } => return "string literal".to_string(),
+        Char | Byte => return "character literal".to_string(),
+        Lifetime => return "lifetime".to_string(),
+        ParenOpen => "(",
+        ParenClose => ")",
+        BraceOpen => "{",
+        BraceClose => "}",
+        BracketOpen => "[",
+        BracketClose => "]",
+        Colon => ":",
+        ColonColon => "::",
+        Semicolon => ";",
+        Comma => ",",
+        Dot => ".",
+        DotDot => "..",
+        DotDotEqual => "..=",
+        Arrow => "->",
+        FatArrow => "=>",
+        Question => "?",
+        Pound => "#",
+        At => "@",
+        Equal => "=",
+        EqualEqual => "==",
+        Bang => "!",
+        BangEqual => "!=",
+        Less => "<",
+        LessEqual => "<=",
+        Greater => ">",
+        GreaterEqual => ">=",
+        Plus => "+",
+        PlusEqual => "+=",
+        Minus => "-",
+        MinusEqual => "-=",
+        Star => "*",
+        StarEqual => "*=",
+        Slash => "/",
+        SlashEqual => "/=",
+        Percent => "%",
+        PercentEqual => "%=",
+        Caret => "^",
+        CaretEqual => "^=",
+        Amp => "&",
+        AmpAmp => "&&",
+        AmpEqual => "&=",
+        Pipe => "|",
+        PipePipe => "||",
+        PipeEqual => "|=",
+        LessLess => "<<",
+        LessLessEqual => "<<=",
+        GreaterGreater => ">>",
+        GreaterGreaterEqual => ">>=",
+        Eof => return "end of file".to_string(),
+    };
+    format!("`{}`", text)
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expected::Token(kind) => write!(f, "{}", describe(*kind)),
+            Expected::Syntax(what) => write!(f, "{}", what),
+        }
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseErrorKind::Expected {
+                expected,
+                after,
+                found,
+            } => {
+                write!(f, "expected ")?;
+                for (i, e) in expected.iter().enumerate() {
+                    match i {
+                        0 => {}
+                        _ if i + 1 == expected.len() => write!(f, " or ")?,
+                        _ => write!(f, ", ")?,
+                    }
+                    write!(f, "{}", e)?;
+                }
+                if let Some(after) = after {
+                    write!(f, " after {}", after)?;
+                }
+                write!(f, ", found {}", describe(*found))
+            }
+            ParseErrorKind::ChainedComparison => {
+                write!(f, "comparison operators cannot be chained")
+            }
+            ParseErrorKind::MissingTurbofish => write!(
+                f,
+                "comparison operators cannot be chained; use `::<...>` for generic arguments"
+            ),
+            ParseErrorKind::ChainedRange => write!(f, "range operators cannot be chained"),
+            ParseErrorKind::InvalidTupleIndex => write!(f, "invalid tuple index"),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.kind.fmt(f)
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,1078 @@
+//! Expressions, parsed by precedence climbing over the `INFIX` table.
+
+use yuri_lexer::{Base, Keyword, Span, Token, TokenKind};
+
+use crate::ast::LitKind;
+use crate::parser::{PResult, Parser};
+use crate::path::PathStyle;
+use crate::syntax::{Checkpoint, NodeKind};
+use crate::{Expected, ParseError, ParseErrorKind};
+
+/// Binding power of infix operators, from loosest to tightest.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Prec {
+    Assign,
+    Range,
+    Or,
+    And,
+    Compare,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Shift,
+    Sum,
+    Product,
+    Cast,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Assoc {
+    Left,
+    Right,
+    /// Chaining is an error: `a == b == c`.
+    None,
+}
+
+impl Prec {
+    fn assoc(self) -> Assoc {
+        match self {
+            Prec::Assign => Assoc::Right,
+            Prec::Range | Prec::Compare => Assoc::None,
+            _ => Assoc::Left,
+        }
+    }
+
+    /// The next tighter precedence.
+    fn next(self) -> Prec {
+        match self {
+            Prec::Assign => Prec::Range,
+            Prec::Range => Prec::Or,
+            Prec::Or => Prec::And,
+            Prec::And => Prec::Compare,
+            Prec::Compare => Prec::BitOr,
+            Prec::BitOr => Prec::BitXor,
+            Prec::BitXor => Prec::BitAnd,
+            Prec::BitAnd => Prec::Shift,
+            Prec::Shift => Prec::Sum,
+            Prec::Sum => Prec::Product,
+            Prec::Product | Prec::Cast => Prec::Cast,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Infix {
+    Binary,
+    /// `=` and compound assignments such as `+=`.
+    Assign,
+    Range {
+        inclusive: bool,
+    },
+    Cast,
+}
+
+/// Every infix operator with its precedence. Prefix operators bind tighter
+/// than all of these, and postfix ones tighter still.
+const INFIX: &[(TokenKind, Infix, Prec)] = &[
+    (TokenKind::Equal, Infix::Assign, Prec::Assign),
+    (TokenKind::PlusEqual, Infix::Assign, Prec::Assign),
+    (TokenKind::MinusEqual, Infix::Assign, Prec::Assign),
+    (TokenKind::StarEqual, Infix::Assign, Prec::Assign),
+    (TokenKind::SlashEqual, Infix::Assign, Prec::Assign),
+    (TokenKind::PercentEqual, Infix::Assign, Prec::Assign),
+    (TokenKind::CaretEqual, Infix::Assign, Prec::Assign),
+    (TokenKind::AmpEqual, Infix::Assign, Prec::Assign),
+    (TokenKind::PipeEqual, Infix::Assign, Prec::Assign),
+    (TokenKind::LessLessEqual, Infix::Assign, Prec::Assign),
+    (TokenKind::GreaterGreaterEqual, Infix::Assign, Prec::Assign),
+    (
+        TokenKind::DotDot,
+        Infix::Range { inclusive: false },
+        Prec::Range,
+    ),
+    (
+        TokenKind::DotDotEqual,
+        Infix::Range { inclusive: true },
+        Prec::Range,
+    ),
+    (TokenKind::PipePipe, Infix::Binary, Prec::Or),
+    (TokenKind::AmpAmp, Infix::Binary, Prec::And),
+    (TokenKind::EqualEqual, Infix::Binary, Prec::Compare),
+    (TokenKind::BangEqual, Infix::Binary, Prec::Compare),
+    (TokenKind::Less, Infix::Binary, Prec::Compare),
+    (TokenKind::LessEqual, Infix::Binary, Prec::Compare),
+    (TokenKind::Greater, Infix::Binary, Prec::Compare),
+    (TokenKind::GreaterEqual, Infix::Binary, Prec::Compare),
+    (TokenKind::Pipe, Infix::Binary, Prec::BitOr),
+    (TokenKind::Caret, Infix::Binary, Prec::BitXor),
+    (TokenKind::Amp, Infix::Binary, Prec::BitAnd),
+    (TokenKind::LessLess, Infix::Binary, Prec::Shift),
+    (TokenKind::GreaterGreater, Infix::Binary, Prec::Shift),
+    (TokenKind::Plus, Infix::Binary, Prec::Sum),
+    (TokenKind::Minus, Infix::Binary, Prec::Sum),
+    (TokenKind::Star, Infix::Binary, Prec::Product),
+    (TokenKind::Slash, Infix::Binary, Prec::Product),
+    (TokenKind::Percent, Infix::Binary, Prec::Product),
+    (TokenKind::Keyword(Keyword::As), Infix::Cast, Prec::Cast),
+];
+
+fn infix(kind: TokenKind) -> Option<(Infix, Prec)> {
+    INFIX
+        .iter()
+        .find(|(k, _, _)| *k == kind)
+        .map(|&(_, infix, prec)| (infix, prec))
+}
+
+pub(crate) fn lit_kind(kind: TokenKind) -> Option<LitKind> {
+    Some(match kind {
+        TokenKind::Keyword(Keyword::True) => LitKind::Bool(true),
+        TokenKind::Keyword(Keyword::False) => LitKind::Bool(false),
+        TokenKind::Int { base, suffix } => LitKind::Int { base, suffix },
+        TokenKind::Float { suffix } => LitKind::Float { suffix },
+        TokenKind::Duration(unit) => LitKind::Duration(unit),
+        TokenKind::Size(unit) => LitKind::Size(unit),
+        TokenKind::Str => LitKind::Str,
+        TokenKind::ByteStr => LitKind::ByteStr,
+        TokenKind::RawStr { hashes } => LitKind::RawStr { hashes },
+        TokenKind::RawByteStr { hashes } => LitKind::RawByteStr { hashes },
+        TokenKind::Char => LitKind::Char,
+        TokenKind::Byte => LitKind::Byte,
+        _ => return None,
+    })
+}
+
+/// The expression parsing functions return the kind of the node they
+/// built, which is all that the grammar needs to know about it.
+impl<'a> Parser<'a> {
+    pub(crate) fn expr(&mut self) -> PResult<NodeKind> {
+        self.expr_prec(Prec::Assign)
+    }
+
+    /// An expression whose infix operators bind at least as tightly as
+    /// `min`.
+    fn expr_prec(&mut self, min: Prec) -> PResult<NodeKind> {
+        let cp = self.checkpoint();
+        let lhs = if matches!(self.peek(), TokenKind::DotDot | TokenKind::DotDotEqual) {
+            let inclusive = self.bump().kind == TokenKind::DotDotEqual;
+            self.range_end(cp, inclusive)?;
+            self.check_chain(Prec::Range, false)?;
+            NodeKind::RangeExpr
+        } else {
+            self.prefix_expr()?
+        };
+        self.infix_rest(cp, lhs, min)
+    }
+
+    fn infix_rest(&mut self, cp: Checkpoint, mut lhs: NodeKind, min: Prec) -> PResult<NodeKind> {
+        while let Some((op, prec)) = infix(self.peek()) {
+            if prec < min {
+                break;
+            }
+            let op_kind = self.bump().kind;
+            let rhs_min = match prec.assoc() {
+                Assoc::Right => prec,
+                Assoc::Left | Assoc::None => prec.next(),
+            };
+            // `Vec<u8>` in an expression, most likely meant as a path.
+            let mut generic_path = false;
+            lhs = match op {
+                Infix::Range { inclusive } => {
+                    self.range_end(cp, inclusive)?;
+                    NodeKind::RangeExpr
+                }
+                Infix::Cast => {
+                    self.ty()?;
+                    self.wrap(cp, NodeKind::CastExpr);
+                    NodeKind::CastExpr
+                }
+                Infix::Binary => {
+                    let rhs = self.expr_prec(rhs_min)?;
+                    self.wrap(cp, NodeKind::BinaryExpr);
+                    generic_path = op_kind == TokenKind::Less
+                        && lhs == NodeKind::PathExpr
+                        && rhs == NodeKind::PathExpr;
+                    NodeKind::BinaryExpr
+                }
+                Infix::Assign => {
+                    self.expr_prec(rhs_min)?;
+                    self.wrap(cp, NodeKind::AssignExpr);
+                    NodeKind::AssignExpr
+                }
+            };
+            if prec.assoc() == Assoc::None {
+                self.check_chain(prec, generic_path)?;
+            }
+        }
+        Ok(lhs)
+    }
+
+    /// Rejects a second non-associative operator of precedence `prec`.
+    /// `generic_path` tells whether the first was the `<` of what looks
+    /// like a path with generic arguments.
+    fn check_chain(&self, prec: Prec, generic_path: bool) -> PResult<()> {
+        match infix(self.peek()) {
+            Some((_, next)) if next == prec => {
+                let kind = if prec == Prec::Range {
+                    ParseErrorKind::ChainedRange
+                } else if generic_path && self.at(TokenKind::Greater) {
+                    // `Vec<u8>::new()`
+                    ParseErrorKind::MissingTurbofish
+                } else {
+                    ParseErrorKind::ChainedComparison
+                };
+                Err(ParseError::new(kind, self.span()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The rest of a range after its `..` or `..=`, making a range of
+    /// everything since `cp`. The end is optional unless the range is
+    /// inclusive.
+    fn range_end(&mut self, cp: Checkpoint, inclusive: bool) -> PResult<()> {
+        if self.can_begin_expr() {
+            self.expr_prec(Prec::Range.next())?;
+        } else if inclusive {
+            return Err(self.expected(Expected::Syntax("expression")));
+        }
+        self.wrap(cp, NodeKind::RangeExpr);
+        Ok(())
+    }
+
+    pub(crate) fn can_begin_expr(&self) -> bool {
+        match self.peek() {
+            TokenKind::Ident
+            | TokenKind::ParenOpen
+            | TokenKind::BracketOpen
+            | TokenKind::Minus
+            | TokenKind::Bang
+            | TokenKind::Star
+            | TokenKind::Amp
+            | TokenKind::AmpAmp
+            | TokenKind::DotDot
+            | TokenKind::DotDotEqual
+            | TokenKind::Error => true,
+            TokenKind::BraceOpen => self.struct_allowed(),
+            TokenKind::Keyword(keyword) => matches!(
+                keyword,
+                Keyword::SelfValue
+                    | Keyword::SelfType
+                    | Keyword::Super
+                    | Keyword::Crate
+                    | Keyword::If
+                    | Keyword::Loop
+                    | Keyword::While
+                    | Keyword::Match
+                    | Keyword::Break
+                    | Keyword::Continue
+                    | Keyword::Return
+                    | Keyword::Yield
+                    | Keyword::True
+                    | Keyword::False
+            ),
+            kind => lit_kind(kind).is_some(),
+        }
+    }
+
+    fn prefix_expr(&mut self) -> PResult<NodeKind> {
+        let cp = self.checkpoint();
+        let kind = match self.peek() {
+            TokenKind::Minus | TokenKind::Bang | TokenKind::Star => {
+                self.bump();
+                self.prefix_expr()?;
+                NodeKind::UnaryExpr
+            }
+            // `&&x` is a reference to a reference.
+            TokenKind::Amp | TokenKind::AmpAmp => {
+                self.eat_split(TokenKind::Amp);
+                self.eat_quietly(TokenKind::Keyword(Keyword::Mut));
+                self.prefix_expr()?;
+                NodeKind::RefExpr
+            }
+            _ => {
+                let kind = self.primary_expr()?;
+                return self.postfix_rest(cp, kind);
+            }
+        };
+        self.wrap(cp, kind);
+        Ok(kind)
+    }
+
+    /// Calls, indexing, field accesses, method calls and `?` on the
+    /// expression of `kind` parsed since `cp`.
+    fn postfix_rest(&mut self, cp: Checkpoint, mut kind: NodeKind) -> PResult<NodeKind> {
+        loop {
+            kind = match self.peek() {
+                TokenKind::ParenOpen => {
+                    self.call_args()?;
+                    NodeKind::CallExpr
+                }
+                TokenKind::BracketOpen => {
+                    self.bump();
+                    self.with_structs(true, |p| p.expr())?;
+                    self.expect(TokenKind::BracketClose)?;
+                    NodeKind::IndexExpr
+                }
+                TokenKind::Dot => {
+                    self.bump();
+                    self.dot_suffix()?
+                }
+                TokenKind::Question => {
+                    self.bump();
+                    NodeKind::TryExpr
+                }
+                _ => return Ok(kind),
+            };
+            self.wrap(cp, kind);
+        }
+    }
+
+    /// `(a, b)` after a callee.
+    fn call_args(&mut self) -> PResult<()> {
+        self.expect(TokenKind::ParenOpen)?;
+        self.with_structs(true, |p| p.comma_list(TokenKind::ParenClose, |p| p.expr()))?;
+        Ok(())
+    }
+
+    /// A field access, tuple index or method call after a `.`, returning
+    /// the kind of node to make of everything since the receiver.
+    fn dot_suffix(&mut self) -> PResult<NodeKind> {
+        let token = self.token();
+        match token.kind {
+            TokenKind::Ident => {
+                self.name(NodeKind::NameRef)?;
+                let generic = self.eat(TokenKind::ColonColon);
+                if generic {
+                    self.generic_args()?;
+                }
+                if generic || self.check(TokenKind::ParenOpen) {
+                    self.call_args()?;
+                    Ok(NodeKind::MethodCallExpr)
+                } else {
+                    Ok(NodeKind::FieldExpr)
+                }
+            }
+            TokenKind::Int {
+                base: Base::Decimal,
+                suffix: None,
+            } => {
+                self.node(NodeKind::NameRef, |p| {
+                    p.bump();
+                    Ok(())
+                })?;
+                Ok(NodeKind::FieldExpr)
+            }
+            // `t.0.1` is lexed with a float `0.1`, which is two tuple indices.
+            TokenKind::Float { suffix: None }
+                if token.text.bytes().all(|b| b.is_ascii_digit() || b == b'.') =>
+            {
+                self.split_float(token);
+                self.dot_suffix()
+            }
+            TokenKind::Int { .. } | TokenKind::Float { .. } => Err(ParseError::new(
+                ParseErrorKind::InvalidTupleIndex,
+                token.span,
+            )),
+            _ => Err(self.expected(Expected::Syntax("field name"))),
+        }
+    }
+
+    /// Splits the current token, a float such as `0.1` after a `.`, into
+    /// the tuple indices and the `.` between them that it stands for.
+    fn split_float(&mut self, token: Token<'a>) {
+        let dot = token.text.find('.').unwrap();
+        let Span { file, lo, hi } = token.span;
+        let mid = lo + dot as u32;
+        let int = TokenKind::Int {
+            base: Base::Decimal,
+            suffix: None,
+        };
+        let mut pieces = vec![
+            Token {
+                kind: int,
+                span: Span::new(file, lo, mid),
+                text: &token.text[..dot],
+                symbol: None,
+            },
+            Token {
+                kind: TokenKind::Dot,
+                span: Span::new(file, mid, mid + 1),
+                text: ".",
+                symbol: None,
+            },
+        ];
+        if mid + 1 < hi {
+            pieces.push(Token {
+                kind: int,
+                span: Span::new(file, mid + 1, hi),
+                text: &token.text[dot + 1..],
+                symbol: None,
+            });
+        }
+        self.split_token(pieces);
+    }
+
+    /// Whether a literal or a negated number literal such as `-1` is next,
+    /// as allowed in patterns and const generic arguments.
+    pub(crate) fn at_lit_expr(&self) -> bool {
+        match self.peek() {
+            TokenKind::Minus => {
+                matches!(self.nth(1), TokenKind::Int { .. } | TokenKind::Float { .. })
+            }
+            kind => lit_kind(kind).is_some(),
+        }
+    }
+
+    /// A literal or a negated number literal. Only call this `at_lit_expr`.
+    pub(crate) fn lit_expr(&mut self) -> NodeKind {
+        let cp = self.checkpoint();
+        let negated = self.eat_quietly(TokenKind::Minus);
+        self.lit();
+        if negated {
+            self.wrap(cp, NodeKind::UnaryExpr);
+            NodeKind::UnaryExpr
+        } else {
+            NodeKind::LitExpr
+        }
+    }
+
+    fn lit(&mut self) {
+        self.start_node(NodeKind::LitExpr);
+        self.bump();
+        self.finish_node();
+    }
+
+    fn primary_expr(&mut self) -> PResult<NodeKind> {
+        let cp = self.checkpoint();
+        let kind = match self.peek() {
+            kind if lit_kind(kind).is_some() => {
+                self.lit();
+                return Ok(NodeKind::LitExpr);
+            }
+            _ if self.at_path_start() => {
+                self.path(PathStyle::Expr)?;
+                if self.struct_allowed() && self.at(TokenKind::BraceOpen) {
+                    self.struct_expr()?;
+                    NodeKind::StructExpr
+                } else {
+                    NodeKind::PathExpr
+                }
+            }
+            TokenKind::ParenOpen => {
+                self.bump();
+                let (exprs, trailing) = self.with_structs(true, |p| {
+                    p.comma_list_trailing(TokenKind::ParenClose, |p| p.expr())
+                })?;
+                if exprs.len() == 1 && !trailing {
+                    NodeKind::ParenExpr
+                } else {
+                    NodeKind::TupleExpr
+                }
+            }
+            TokenKind::BracketOpen => {
+                self.bump();
+                self.with_structs(true, |p| p.array())?
+            }
+            TokenKind::BraceOpen => {
+                self.block()?;
+                return Ok(NodeKind::Block);
+            }
+            TokenKind::Keyword(Keyword::If) => {
+                self.if_expr()?;
+                return Ok(NodeKind::IfExpr);
+            }
+            TokenKind::Keyword(Keyword::Loop) => {
+                self.bump();
+                self.block()?;
+                NodeKind::LoopExpr
+            }
+            TokenKind::Keyword(Keyword::While) => {
+                self.bump();
+                self.cond()?;
+                self.block()?;
+                NodeKind::WhileExpr
+            }
+            TokenKind::Keyword(Keyword::Match) => {
+                self.match_expr()?;
+                return Ok(NodeKind::MatchExpr);
+            }
+            TokenKind::Keyword(Keyword::Break) => {
+                self.bump();
+                self.opt_operand()?;
+                NodeKind::BreakExpr
+            }
+            TokenKind::Keyword(Keyword::Continue) => {
+                self.bump();
+                NodeKind::ContinueExpr
+            }
+            TokenKind::Keyword(Keyword::Return) => {
+                self.bump();
+                self.opt_operand()?;
+                NodeKind::ReturnExpr
+            }
+            TokenKind::Keyword(Keyword::Yield) => {
+                self.bump();
+                NodeKind::YieldExpr
+            }
+            // Text that did not lex, which the lexer has reported.
+            TokenKind::Error => {
+                self.bump();
+                NodeKind::Error
+            }
+            _ => return Err(self.expected(Expected::Syntax("expression"))),
+        };
+        self.wrap(cp, kind);
+        Ok(kind)
+    }
+
+    /// The value of a `break` or `return`, if any.
+    fn opt_operand(&mut self) -> PResult<()> {
+        if self.can_begin_expr() {
+            self.expr()?;
+        }
+        Ok(())
+    }
+
+    /// The rest of an array expression after its `[`.
+    fn array(&mut self) -> PResult<NodeKind> {
+        if self.eat(TokenKind::BracketClose) {
+            return Ok(NodeKind::ArrayExpr);
+        }
+        self.expr()?;
+        if self.eat(TokenKind::Semicolon) {
+            self.expr()?;
+            self.expect(TokenKind::BracketClose)?;
+            return Ok(NodeKind::RepeatExpr);
+        }
+        if self.eat(TokenKind::Comma) {
+            self.comma_list(TokenKind::BracketClose, |p| p.expr())?;
+        } else {
+            self.expect(TokenKind::BracketClose)?;
+        }
+        Ok(NodeKind::ArrayExpr)
+    }
+
+    /// The fields of a struct expression after its path: `{ a: x, b }`.
+    fn struct_expr(&mut self) -> PResult<()> {
+        self.expect(TokenKind::BraceOpen)?;
+        self.with_structs(true, |p| {
+            p.comma_list(TokenKind::BraceClose, |p| {
+                p.node(NodeKind::ExprField, |p| {
+                    p.name(NodeKind::NameRef)?;
+                    if p.eat(TokenKind::Colon) {
+                        p.expr()?;
+                    }
+                    Ok(())
+                })
+            })
+        })?;
+        Ok(())
+    }
+
+    /// The condition of an `if` or `while`, which may be `let pat = expr`.
+    fn cond(&mut self) -> PResult<NodeKind> {
+        self.with_structs(false, |p| {
+            if !p.at(TokenKind::Keyword(Keyword::Let)) {
+                return p.expr();
+            }
+            p.node(NodeKind::LetExpr, |p| {
+                p.bump();
+                p.pat()?;
+                p.expect(TokenKind::Equal)?;
+                p.expr()?;
+                Ok(NodeKind::LetExpr)
+            })
+        })
+    }
+
+    fn if_expr(&mut self) -> PResult<()> {
+        self.node(NodeKind::IfExpr, |p| {
+            p.bump();
+            p.cond()?;
+            p.block()?;
+            if p.eat_keyword(Keyword::Else) {
+                if p.check_keyword(Keyword::If) {
+                    p.if_expr()?;
+                } else {
+                    p.block()?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    fn match_expr(&mut self) -> PResult<()> {
+        self.node(NodeKind::MatchExpr, |p| {
+            p.bump();
+            p.with_structs(false, |p| p.expr())?;
+            p.expect(TokenKind::BraceOpen)?;
+            while !p.eat(TokenKind::BraceClose) {
+                let body = p.node(NodeKind::Arm, |p| {
+                    p.eat_quietly(TokenKind::Pipe);
+                    p.pat()?;
+                    if p.check_keyword(Keyword::If) {
+                        p.node(NodeKind::MatchGuard, |p| {
+                            p.bump();
+                            p.with_structs(true, |p| p.expr())
+                        })?;
+                    }
+                    p.expect(TokenKind::FatArrow)?;
+                    p.with_structs(true, |p| p.expr_stmt())
+                })?;
+                // Arms end in `,` unless their body is a block or they are
+                // last.
+                if !p.eat(TokenKind::Comma)
+                    && !body.is_block_like()
+                    && !p.check(TokenKind::BraceClose)
+                {
+                    return Err(p.expected(Expected::Token(TokenKind::Comma)));
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// An expression in statement position. One that starts with a block,
+    /// such as `if` or `loop`, ends there unless a method call or field
+    /// access follows, so `loop {} -1` is two statements.
+    pub(crate) fn expr_stmt(&mut self) -> PResult<NodeKind> {
+        let block_like = match self.peek() {
+            TokenKind::BraceOpen => true,
+            TokenKind::Keyword(keyword) => matches!(
+                keyword,
+                Keyword::If | Keyword::Loop | Keyword::While | Keyword::Match
+            ),
+            _ => false,
+        };
+        if !block_like {
+            return self.expr();
+        }
+        let cp = self.checkpoint();
+        let kind = self.primary_expr()?;
+        if self.at(TokenKind::Dot) {
+            let kind = self.postfix_rest(cp, kind)?;
+            self.infix_rest(cp, kind, Prec::Assign)
+        } else {
+            Ok(kind)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::cell::RefCell;
+
+    use yuri_lexer::{FileId, Interner, Lexer};
+
+    use super::*;
+    use crate::ast::{AstNode, Block, Expr, GenericArg, Pat, Path, Stmt, Ty};
+    use crate::syntax::SyntaxNode;
+
+    /// Prints expressions as s-expressions, which makes their structure
+    /// visible.
+    struct Printer;
+
+    impl Printer {
+        fn path(&self, path: &Path) -> String {
+            let segments: Vec<_> = path
+                .segments()
+                .map(|s| match &s.generic_args {
+                    Some(args) => {
+                        let args: Vec<_> = args
+                            .args()
+                            .map(|arg| match arg {
+                                GenericArg::Type(ty) => self.ty(&ty),
+                                GenericArg::Const(expr) => self.expr(&expr),
+                            })
+                            .collect();
+                        format!("{}<{}>", s.name.text(), args.join(", "))
+                    }
+                    None => s.name.text(),
+                })
+                .collect();
+            segments.join("::")
+        }
+
+        fn ty(&self, ty: &Ty) -> String {
+            match ty {
+                Ty::Path(ty) => self.path(&ty.path().unwrap()),
+                Ty::Ref(ty) => format!(
+                    "&{}{}{}",
+                    ty.lifetime()
+                        .map_or(String::new(), |l| format!("{} ", l.text())),
+                    if ty.is_mut() { "mut " } else { "" },
+                    self.ty(&ty.ty().unwrap())
+                ),
+                Ty::Array(ty) => format!(
+                    "[{}; {}]",
+                    self.ty(&ty.elem().unwrap()),
+                    self.expr(&ty.len().unwrap())
+                ),
+                Ty::Slice(ty) => format!("[{}]", self.ty(&ty.elem().unwrap())),
+                Ty::Tuple(ty) => format!("({})", self.tys(ty.fields())),
+                Ty::Fn(ty) => {
+                    let params: Vec<_> = ty
+                        .param_lists()
+                        .into_iter()
+                        .map(|tys| format!("({})", self.tys(tys)))
+                        .collect();
+                    let ret = ty
+                        .ret_ty()
+                        .map_or(String::new(), |ty| format!(" : {}", self.ty(&ty)));
+                    format!("fn {}{}", params.join(" "), ret)
+                }
+            }
+        }
+
+        fn tys(&self, tys: impl IntoIterator<Item = Ty>) -> String {
+            let tys: Vec<_> = tys.into_iter().map(|t| self.ty(&t)).collect();
+            tys.join(", ")
+        }
+
+        fn pats(&self, pats: impl Iterator<Item = Pat>, sep: &str) -> String {
+            let pats: Vec<_> = pats.map(|p| self.pat(&p)).collect();
+            pats.join(sep)
+        }
+
+        fn pat(&self, pat: &Pat) -> String {
+            match pat {
+                Pat::Wild(_) => "_".to_string(),
+                Pat::Rest(_) => "..".to_string(),
+                Pat::Binding(pat) => format!(
+                    "{}{}{}{}",
+                    if pat.is_ref() { "ref " } else { "" },
+                    if pat.is_mut() { "mut " } else { "" },
+                    pat.name().unwrap().text(),
+                    pat.sub_pat()
+                        .map_or(String::new(), |p| format!(" @ {}", self.pat(&p)))
+                ),
+                Pat::Lit(pat) => self.expr(&pat.expr().unwrap()),
+                Pat::Range(pat) => format!(
+                    "{}{}{}",
+                    pat.start().map_or(String::new(), |e| self.expr(&e)),
+                    if pat.is_inclusive() { "..=" } else { ".." },
+                    pat.end().map_or(String::new(), |e| self.expr(&e))
+                ),
+                Pat::Path(pat) => self.path(&pat.path().unwrap()),
+                Pat::TupleStruct(pat) => format!(
+                    "{}({})",
+                    self.path(&pat.path().unwrap()),
+                    self.pats(pat.fields(), ", ")
+                ),
+                Pat::Struct(pat) => {
+                    let mut fields: Vec<_> = pat
+                        .fields()
+                        .map(|f| match f.name_ref() {
+                            Some(name) => {
+                                format!("{}: {}", name.text(), self.pat(&f.pat().unwrap()))
+                            }
+                            None => self.pat(&f.pat().unwrap()),
+                        })
+                        .collect();
+                    if pat.has_rest() {
+                        fields.push("..".to_string());
+                    }
+                    format!(
+                        "{} {{ {} }}",
+                        self.path(&pat.path().unwrap()),
+                        fields.join(", ")
+                    )
+                }
+                Pat::Tuple(pat) => format!("({})", self.pats(pat.fields(), ", ")),
+                Pat::Slice(pat) => format!("[{}]", self.pats(pat.pats(), ", ")),
+                Pat::Or(pat) => format!("({})", self.pats(pat.pats(), " | ")),
+            }
+        }
+
+        fn list(&self, head: &str, exprs: impl IntoIterator<Item = Option<Expr>>) -> String {
+            let mut s = format!("({}", head);
+            for expr in exprs {
+                s.push(' ');
+                s.push_str(&self.expr(&expr.unwrap()));
+            }
+            s.push(')');
+            s
+        }
+
+        fn opt(&self, expr: Option<Expr>) -> String {
+            expr.map_or("_".to_string(), |e| self.expr(&e))
+        }
+
+        fn block(&self, block: &Block) -> String {
+            let stmts: Vec<_> = block
+                .stmts()
+                .map(|stmt| match stmt {
+                    Stmt::Let(stmt) => format!(
+                        "(let {} {})",
+                        self.pat(&stmt.pat().unwrap()),
+                        self.opt(stmt.init())
+                    ),
+                    Stmt::Item(_) => "item".to_string(),
+                    Stmt::Expr(stmt) if stmt.has_semicolon() => {
+                        format!("{};", self.expr(&stmt.expr().unwrap()))
+                    }
+                    Stmt::Expr(stmt) => self.expr(&stmt.expr().unwrap()),
+                })
+                .collect();
+            format!("{{{}}}", stmts.join(" "))
+        }
+
+        fn expr(&self, expr: &Expr) -> String {
+            match expr {
+                Expr::Lit(e) => e.token().text().to_string(),
+                Expr::Path(e) => self.path(&e.path().unwrap()),
+                Expr::Paren(e) => self.list("paren", vec![e.expr()]),
+                Expr::Tuple(e) => self.list("tuple", e.fields().map(Some)),
+                Expr::Array(e) => self.list("array", e.elems().map(Some)),
+                Expr::Repeat(e) => self.list("repeat", vec![e.elem(), e.len()]),
+                Expr::Struct(e) => {
+                    let fields: Vec<_> = e
+                        .fields()
+                        .map(|f| {
+                            let name = f.name_ref().unwrap().text();
+                            // In `S { a }`, `a` is both the name and the value.
+                            let value = f.expr().map_or(name.clone(), |e| self.expr(&e));
+                            format!(" {}: {}", name, value)
+                        })
+                        .collect();
+                    format!(
+                        "(struct {}{})",
+                        self.path(&e.path().unwrap()),
+                        fields.concat()
+                    )
+                }
+                Expr::Unary(e) => self.list(e.op().unwrap().as_str(), vec![e.expr()]),
+                Expr::Binary(e) => self.list(e.op().unwrap().as_str(), vec![e.lhs(), e.rhs()]),
+                Expr::Ref(e) => self.list(if e.is_mut() { "&mut" } else { "&" }, vec![e.expr()]),
+                Expr::Assign(e) => {
+                    let op = e.op().map_or(String::new(), |op| op.as_str().to_string());
+                    self.list(&format!("{}=", op), vec![e.lhs(), e.rhs()])
+                }
+                Expr::Cast(e) => format!(
+                    "(as {} {})",
+                    self.expr(&e.expr().unwrap()),
+                    self.ty(&e.ty().unwrap())
+                ),
+                Expr::Call(e) => {
+                    let mut exprs = vec![e.callee()];
+                    exprs.extend(e.args().map(Some));
+                    self.list("call", exprs)
+                }
+                Expr::MethodCall(e) => {
+                    let mut exprs = vec![e.receiver()];
+                    exprs.extend(e.args().map(Some));
+                    self.list(&format!(".{}", e.name_ref().unwrap().text()), exprs)
+                }
+                Expr::Field(e) => format!(
+                    "(. {} {})",
+                    self.expr(&e.expr().unwrap()),
+                    e.name_ref().unwrap().text()
+                ),
+                Expr::Index(e) => self.list("index", vec![e.base(), e.index()]),
+                Expr::Try(e) => self.list("?", vec![e.expr()]),
+                Expr::Range(e) => format!(
+                    "({} {} {})",
+                    if e.is_inclusive() { "..=" } else { ".." },
+                    self.opt(e.start()),
+                    self.opt(e.end())
+                ),
+                Expr::Block(block) => self.block(block),
+                Expr::If(e) => format!(
+                    "(if {} {} {})",
+                    self.expr(&e.cond().unwrap()),
+                    self.block(&e.then_branch().unwrap()),
+                    self.opt(e.else_branch())
+                ),
+                Expr::Loop(e) => format!("(loop {})", self.block(&e.body().unwrap())),
+                Expr::While(e) => format!(
+                    "(while {} {})",
+                    self.expr(&e.cond().unwrap()),
+                    self.block(&e.body().unwrap())
+                ),
+                Expr::Match(e) => {
+                    let arms: Vec<_> = e
+                        .arms()
+                        .map(|arm| {
+                            let guard = match arm.guard() {
+                                Some(guard) => format!(" if {}", self.opt(guard.cond())),
+                                None => String::new(),
+                            };
+                            format!(
+                                " ({}{} {})",
+                                self.pat(&arm.pat().unwrap()),
+                                guard,
+                                self.expr(&arm.expr().unwrap())
+                            )
+                        })
+                        .collect();
+                    format!(
+                        "(match {}{})",
+                        self.expr(&e.scrutinee().unwrap()),
+                        arms.concat()
+                    )
+                }
+                Expr::Let(e) => format!(
+                    "(let {} {})",
+                    self.pat(&e.pat().unwrap()),
+                    self.expr(&e.expr().unwrap())
+                ),
+                Expr::Break(e) => format!("(break {})", self.opt(e.expr())),
+                Expr::Continue(_) => "continue".to_string(),
+                Expr::Return(e) => format!("(return {})", self.opt(e.expr())),
+                Expr::Yield(_) => "yield".to_string(),
+                Expr::Error(_) => "error".to_string(),
+            }
+        }
+    }
+
+    /// Parses `src` as a single expression, failing with the first syntax
+    /// error if there are any.
+    fn parse(src: &str) -> Result<String, ParseError> {
+        let interner = RefCell::new(Interner::new());
+        let tokens = Lexer::new(src)
+            .with_trivia()
+            .with_interner(&interner)
+            .filter_map(Result::ok)
+            .collect();
+        let mut parser = Parser::new(tokens, &interner);
+        parser.expr()?;
+        parser.expect(TokenKind::Eof)?;
+        let (green, errors) = parser.finish();
+        if let Some(e) = errors.first() {
+            return Err(e.clone());
+        }
+        let expr = Expr::cast(SyntaxNode::new_root(green, FileId::default())).unwrap();
+        Ok(Printer.expr(&expr))
+    }
+
+    pub(crate) fn check(src: &str, expected: &str) {
+        assert_eq!(parse(src).unwrap(), expected, "{}", src);
+    }
+
+    pub(crate) fn check_err(src: &str, expected: &str) {
+        let err = parse(src).unwrap_err();
+        assert_eq!(err.to_string(), expected, "{}", src);
+    }
+
+    #[test]
+    fn precedence() {
+        check("1 + 2 * 3 - 4", "(- (+ 1 (* 2 3)) 4)");
+        check("a - b - c", "(- (- a b) c)");
+        check("a / b % c * d", "(* (% (/ a b) c) d)");
+        check("a | b ^ c & d << e + f", "(| a (^ b (& c (<< d (+ e f)))))");
+        check("a == b & c", "(== a (& b c))");
+        check("a || b && c || d", "(|| (|| a (&& b c)) d)");
+        check("a < b && c >= d", "(&& (< a b) (>= c d))");
+        check("(a + b) * c", "(* (paren (+ a b)) c)");
+    }
+
+    #[test]
+    fn assignment_is_right_associative() {
+        check("a = b = c", "(= a (= b c))");
+        check("a += b -= c", "(+= a (-= b c))");
+        check("x <<= 1 + 2", "(<<= x (+ 1 2))");
+        check("st.buf = [0u8; 1024]", "(= (. st buf) (repeat 0u8 1024))");
+    }
+
+    #[test]
+    fn unary_and_postfix() {
+        check("-a.b(c)[0]", "(- (index (.b a c) 0))");
+        check("!*&mut x", "(! (* (&mut x)))");
+        check("&&x", "(& (& x))");
+        check("- -1", "(- (- 1))");
+        check("f(a)(b)", "(call (call f a) b)");
+        check("x.0.1", "(. (. x 0) 1)");
+        check("x.0 .1", "(. (. x 0) 1)");
+        check("s.len::<u8>()", "(.len s)");
+        check("Poll::Ready(())", "(call Poll::Ready (tuple))");
+        check("Vec::<u8>::new()", "(call Vec<u8>::new)");
+        check("f()?", "(? (call f))");
+        check("-a.b()?.c?", "(- (? (. (? (.b a)) c)))");
+        check("x?[0] as u8", "(as (index (? x) 0) u8)");
+    }
+
+    #[test]
+    fn casts() {
+        check("-x as u8 as u32 * 2", "(* (as (as (- x) u8) u32) 2)");
+        check("a + b as i64", "(+ a (as b i64))");
+        check("p as &mut [u8; 4]", "(as p &mut [u8; 4])");
+    }
+
+    #[test]
+    fn ranges() {
+        check("&buf[0..n]", "(& (index buf (.. 0 n)))");
+        check("a..b + 1", "(.. a (+ b 1))");
+        check("a || b..c && d", "(.. (|| a b) (&& c d))");
+        check("..", "(.. _ _)");
+        check("a..", "(.. a _)");
+        check("..=b", "(..= _ b)");
+        check("x = a..b", "(= x (.. a b))");
+        check("f(..)", "(call f (.. _ _))");
+    }
+
+    #[test]
+    fn non_associative_operators() {
+        check_err("a < b < c", "comparison operators cannot be chained");
+        check_err("a == b != c", "comparison operators cannot be chained");
+        check_err("a..b..c", "range operators cannot be chained");
+        check_err("..a..b", "range operators cannot be chained");
+        check_err("a..=", "expected expression, found end of file");
+        check("(a < b) < c", "(< (paren (< a b)) c)");
+    }
+
+    #[test]
+    fn struct_literals_in_conditions() {
+        check("S { a, b: 1 }", "(struct S a: a b: 1)");
+        check("if x == S {}", "(if (== x S) {} _)");
+        check(
+            "if x == (S { a }) {}",
+            "(if (== x (paren (struct S a: a))) {} _)",
+        );
+        check("while f(S { a }) {}", "(while (call f (struct S a: a)) {})");
+        check("match s { _ => 1 }", "(match s (_ 1))");
+        check(
+            "if a { b } else if c { d } else { e }",
+            "(if a {b} (if c {d} {e}))",
+        );
+        check(
+            "if x { S { a: 1 } } else { [S {}] }",
+            "(if x {(struct S a: 1)} {(array (struct S))})",
+        );
+    }
+
+    #[test]
+    fn blocks_and_statements() {
+        check(
+            "loop { let n = read(src, &mut buf); if n == 0 { break; } write(dest, &buf[0..n]); }",
+            "(loop {(let n (call read src (&mut buf))) (if (== n 0) {(break _);} _) (call write dest (& (index buf (.. 0 n))));})",
+        );
+        check(
+            "loop { match s.poll() { Poll::Ready(n) => break n, Poll::Pending => yield, } }",
+            "(loop {(match (.poll s) (Poll::Ready(n) (break n)) (Poll::Pending yield))})",
+        );
+        check("{ loop {} -1 }", "{(loop {}) (- 1)}");
+        check("{ match x {}.len() }", "{(.len (match x))}");
+        check(
+            "match x { 1 | 2 if y => {}, _ => () }",
+            "(match x ((1 | 2) if y {}) (_ (tuple)))",
+        );
+        check(
+            "match x { Some(n) if n == S { a } => n, _ => 0 }",
+            "(match x (Some(n) if (== n (struct S a: a)) n) (_ 0))",
+        );
+        check_err("match x { _ if => 1 }", "expected expression, found `=>`");
+        check("{ return Poll::Pending; }", "{(return Poll::Pending);}");
+        check_err(
+            "{ a b }",
+            "expected `;` or `}` after expression, found identifier",
+        );
+    }
+
+    #[test]
+    fn errors() {
+        check_err("1 +", "expected expression, found end of file");
+        check_err("t.1e3", "invalid tuple index");
+        check_err("f(a b)", "expected `,` or `)`, found identifier");
+        check_err("x.", "expected field name, found end of file");
+    }
+}
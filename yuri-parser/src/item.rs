@@ -0,0 +1,634 @@
+use yuri_lexer::{Keyword, TokenKind};
+
+use crate::parser::{PResult, Parser};
+use crate::path::PathStyle;
+use crate::recovery::Sync;
+use crate::syntax::NodeKind;
+use crate::{Expected, ParseError, ParseErrorKind};
+
+impl Parser<'_> {
+    /// The whole input as a source file. Syntax errors are collected in
+    /// `errors` rather than ending the parse.
+    pub(crate) fn source_file(&mut self) {
+        self.start_node(NodeKind::SourceFile);
+        self.items(TokenKind::Eof);
+        // Trivia at the end of the file, such as a final comment.
+        self.trivia();
+        self.finish_node();
+    }
+
+    /// Items up to and including `close`. An item with a syntax error is
+    /// reported and replaced by an `Error` node, so that the items after it
+    /// are still parsed.
+    fn items(&mut self, close: TokenKind) {
+        loop {
+            // The end of the file goes without saying in error messages.
+            let done = if close == TokenKind::Eof {
+                self.at(close)
+            } else {
+                self.eat(close)
+            };
+            if done {
+                break;
+            }
+            if self.at(TokenKind::Eof) {
+                let e = self.unexpected(None);
+                self.report(e);
+                break;
+            }
+            let lo = self.span();
+            let cp = self.checkpoint();
+            // Text that did not lex, which the lexer has reported.
+            if self.eat_quietly(TokenKind::Error) {
+                self.wrap(cp, NodeKind::Error);
+                continue;
+            }
+            if let Err(e) = self.item() {
+                self.report(e);
+                self.recover(lo, Sync::Item { close });
+                self.error_node(cp);
+            }
+        }
+    }
+
+    pub(crate) fn at_item_start(&self) -> bool {
+        match self.peek() {
+            TokenKind::Keyword(keyword) => matches!(
+                keyword,
+                Keyword::Pub
+                    | Keyword::Fn
+                    | Keyword::Struct
+                    | Keyword::Enum
+                    | Keyword::Impl
+                    | Keyword::Trait
+                    | Keyword::Use
+                    | Keyword::Mod
+            ),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn item(&mut self) -> PResult<()> {
+        let cp = self.checkpoint();
+        self.visibility()?;
+        let kind = match self.peek() {
+            TokenKind::Keyword(Keyword::Fn) => NodeKind::Fn,
+            TokenKind::Keyword(Keyword::Struct) => NodeKind::Struct,
+            TokenKind::Keyword(Keyword::Enum) => NodeKind::Enum,
+            TokenKind::Keyword(Keyword::Impl) => NodeKind::Impl,
+            TokenKind::Keyword(Keyword::Trait) => NodeKind::Trait,
+            TokenKind::Keyword(Keyword::Use) => NodeKind::Use,
+            TokenKind::Keyword(Keyword::Mod) => NodeKind::Mod,
+            _ => return Err(self.expected(Expected::Syntax("item"))),
+        };
+        // The visibility is part of the item.
+        self.start_node_at(cp, kind);
+        self.bump();
+        match kind {
+            NodeKind::Fn => self.fn_rest()?,
+            NodeKind::Struct => self.struct_rest()?,
+            NodeKind::Enum => self.enum_rest()?,
+            NodeKind::Impl => self.impl_rest()?,
+            NodeKind::Trait => {
+                self.name(NodeKind::Name)?;
+                self.generic_params()?;
+                self.expect(TokenKind::BraceOpen)?;
+                self.items(TokenKind::BraceClose);
+            }
+            NodeKind::Use => {
+                self.use_tree()?;
+                self.expect(TokenKind::Semicolon)?;
+            }
+            _ => {
+                self.name(NodeKind::Name)?;
+                if !self.eat(TokenKind::Semicolon) {
+                    self.expect(TokenKind::BraceOpen)?;
+                    self.items(TokenKind::BraceClose);
+                }
+            }
+        }
+        self.finish_node();
+        Ok(())
+    }
+
+    /// `pub`, `pub(crate)` or nothing.
+    fn visibility(&mut self) -> PResult<()> {
+        if !self.eat_quietly(TokenKind::Keyword(Keyword::Pub)) {
+            return Ok(());
+        }
+        if self.at(TokenKind::ParenOpen) && self.nth(1) == TokenKind::Keyword(Keyword::Crate) {
+            self.bump();
+            self.bump();
+            self.expect(TokenKind::ParenClose)?;
+        }
+        Ok(())
+    }
+
+    /// `<T, U: Bound + Other>`, or nothing.
+    fn generic_params(&mut self) -> PResult<()> {
+        if !self.eat(TokenKind::Less) {
+            return Ok(());
+        }
+        self.comma_list(TokenKind::Greater, |p| {
+            p.node(NodeKind::GenericParam, |p| {
+                if p.check(TokenKind::Lifetime) {
+                    p.node(NodeKind::Name, |p| {
+                        p.bump();
+                        Ok(())
+                    })?;
+                    // A lifetime can only be bounded by other lifetimes.
+                    if p.eat(TokenKind::Colon) {
+                        p.expect(TokenKind::Lifetime)?;
+                        while p.eat(TokenKind::Plus) {
+                            p.expect(TokenKind::Lifetime)?;
+                        }
+                    }
+                    return Ok(());
+                }
+                if p.eat_keyword(Keyword::Const) {
+                    p.name(NodeKind::Name)?;
+                    p.expect(TokenKind::Colon)?;
+                    return p.ty().map(drop);
+                }
+                p.name(NodeKind::Name)?;
+                if p.eat(TokenKind::Colon) {
+                    loop {
+                        if !p.eat(TokenKind::Lifetime) {
+                            p.path(PathStyle::Type)?;
+                        }
+                        if !p.eat(TokenKind::Plus) {
+                            break;
+                        }
+                    }
+                }
+                Ok(())
+            })
+        })?;
+        Ok(())
+    }
+
+    /// The rest of a function after `fn`.
+    fn fn_rest(&mut self) -> PResult<()> {
+        self.name(NodeKind::Name)?;
+        self.generic_params()?;
+        self.param_list()?;
+        while self.check(TokenKind::ParenOpen) {
+            self.param_list()?;
+        }
+        if self.eat(TokenKind::Colon) {
+            self.ty()?;
+        }
+        if !self.eat(TokenKind::Semicolon) {
+            self.block()?;
+        }
+        Ok(())
+    }
+
+    /// `(a: A, b: B)`
+    fn param_list(&mut self) -> PResult<()> {
+        self.node(NodeKind::ParamList, |p| {
+            p.expect(TokenKind::ParenOpen)?;
+            p.comma_list(TokenKind::ParenClose, |p| {
+                if p.at_self_param() {
+                    return p.node(NodeKind::SelfParam, |p| {
+                        while !p.at(TokenKind::Keyword(Keyword::SelfValue)) {
+                            p.bump();
+                        }
+                        p.node(NodeKind::Name, |p| {
+                            p.bump();
+                            Ok(())
+                        })
+                    });
+                }
+                p.node(NodeKind::Param, |p| {
+                    p.pat_no_alt()?;
+                    p.expect_after(TokenKind::Colon, "parameter name")?;
+                    p.ty()?;
+                    Ok(())
+                })
+            })?;
+            Ok(())
+        })
+    }
+
+    /// Whether `self`, `mut self`, `&self` or `&mut self` is next.
+    fn at_self_param(&self) -> bool {
+        let self_value = TokenKind::Keyword(Keyword::SelfValue);
+        let mut_ = TokenKind::Keyword(Keyword::Mut);
+        match (self.peek(), self.nth(1), self.nth(2)) {
+            (kind, _, _) if kind == self_value => true,
+            (TokenKind::Amp, kind, _) if kind == self_value => true,
+            (TokenKind::Amp, m, kind) if m == mut_ && kind == self_value => true,
+            (m, kind, _) => m == mut_ && kind == self_value,
+        }
+    }
+
+    /// The rest of a struct after `struct`.
+    fn struct_rest(&mut self) -> PResult<()> {
+        self.name(NodeKind::Name)?;
+        self.generic_params()?;
+        if !self.fields()? {
+            self.expect(TokenKind::Semicolon)?;
+        }
+        Ok(())
+    }
+
+    /// `{ a: A, b: B }`, `(A, B)` or nothing. Returns whether the fields
+    /// were named.
+    fn fields(&mut self) -> PResult<bool> {
+        if self.eat(TokenKind::ParenOpen) {
+            self.comma_list(TokenKind::ParenClose, |p| p.ty())?;
+            return Ok(false);
+        }
+        if !self.eat(TokenKind::BraceOpen) {
+            return Ok(false);
+        }
+        self.comma_list(TokenKind::BraceClose, |p| {
+            p.node(NodeKind::FieldDef, |p| {
+                p.visibility()?;
+                p.name(NodeKind::Name)?;
+                p.expect_after(TokenKind::Colon, "field name")?;
+                p.ty()?;
+                Ok(())
+            })
+        })?;
+        Ok(true)
+    }
+
+    /// The rest of an enum after `enum`.
+    fn enum_rest(&mut self) -> PResult<()> {
+        self.name(NodeKind::Name)?;
+        self.generic_params()?;
+        self.expect(TokenKind::BraceOpen)?;
+        self.comma_list(TokenKind::BraceClose, |p| {
+            p.node(NodeKind::Variant, |p| {
+                p.name(NodeKind::Name)?;
+                p.fields()?;
+                Ok(())
+            })
+        })?;
+        Ok(())
+    }
+
+    /// The rest of an impl after `impl`.
+    fn impl_rest(&mut self) -> PResult<()> {
+        self.generic_params()?;
+        let lo = self.span();
+        let ty = self.ty()?;
+        let ty_span = self.span_from(lo);
+        if self.eat_keyword(Keyword::For) {
+            if ty != NodeKind::PathTy {
+                let kind = ParseErrorKind::Expected {
+                    expected: vec![Expected::Syntax("trait")],
+                    after: None,
+                    found: TokenKind::Keyword(Keyword::For),
+                };
+                return Err(ParseError::new(kind, ty_span));
+            }
+            self.ty()?;
+        }
+        self.expect(TokenKind::BraceOpen)?;
+        self.items(TokenKind::BraceClose);
+        Ok(())
+    }
+
+    fn use_tree(&mut self) -> PResult<()> {
+        self.node(NodeKind::UseTree, |p| {
+            loop {
+                if p.eat(TokenKind::Star) {
+                    break;
+                }
+                if p.eat(TokenKind::BraceOpen) {
+                    p.comma_list(TokenKind::BraceClose, |p| p.use_tree())?;
+                    break;
+                }
+                p.path_segment()?;
+                if !p.eat(TokenKind::ColonColon) {
+                    if p.eat_keyword(Keyword::As) {
+                        p.name(NodeKind::Name)?;
+                    }
+                    break;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use yuri_lexer::{Interner, Lexer};
+
+    use crate::ast::{
+        AstNode, FieldsKind, GenericParamKind, Item, Pat, SourceFile, Stmt, Ty, UseTree, Visibility,
+    };
+    use crate::ParseError;
+
+    fn parse(src: &str) -> (SourceFile, Vec<ParseError>) {
+        let parse = crate::parse(Lexer::new(src), &RefCell::new(Interner::new()));
+        (parse.source_file(), parse.errors().to_vec())
+    }
+
+    fn parse_ok(src: &str) -> SourceFile {
+        let (file, errors) = parse(src);
+        assert_eq!(errors, vec![], "{}", src);
+        file
+    }
+
+    #[test]
+    fn design_snippets() {
+        let file = parse_ok(
+            "
+fn copy (src: Fd) (dest: Fd) {
+    let mut buf = [0u8; 1024];
+
+    loop {
+        let n = read(src, &mut buf);
+        if n == 0 {
+            break;
+        }
+        write(dest, &buf[0..n]);
+    }
+}
+
+enum copy__state {
+    Init, // 実行前
+    ReadWait, // readの完了待ち
+    WriteWait, // writeの完了待ち
+}
+struct copy__stack {
+    state: copy__state,
+    buf: [u8; 1024],
+    src: Fd,
+    dest: Fd,
+    read__stack: read__stack,
+    write__stack: write__stack,
+}
+
+fn create_copy__stack (src: Fd) (dest: Fd): copy__stack {
+    copy__stack {
+        state: Init,
+        src,
+        dest,
+        // 後は未初期化
+    }
+}
+
+fn step_copy (st: &mut copy_stack): Poll<()> {
+    loop {
+        match st.state {
+            Init => {
+                st.buf = [0u8; 1024];
+                st.read__stack = create_read__stack(st.src, &mut st.buf);
+                st.state = ReadWait;
+            }
+            ReadWait => {
+                let n = match st.read__stack.poll() {
+                    Poll::Ready(n) => n,
+                    Poll::Pending => return Poll::Pending,
+                };
+
+                if n == 0 {
+                    return Poll::Ready(());
+                }
+
+                st.write__stack = create_write__stack(st.dest, &st.buf[0..n]);
+                st.state = WriteWait;
+            }
+            WriteWait => {
+                match st.write__stack.poll() {
+                    Poll::Ready(()) => {},
+                    Poll::Pending => return Poll::Pending,
+                }
+
+                st.read__stack = create_read__stack(st.src, &mut st.buf);
+                st.state = ReadWait;
+            }
+        }
+    }
+}
+",
+        );
+        let items: Vec<_> = file.items().collect();
+        let names: Vec<_> = items
+            .iter()
+            .map(|item| item.name().unwrap().text())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "copy",
+                "copy__state",
+                "copy__stack",
+                "create_copy__stack",
+                "step_copy"
+            ]
+        );
+        match &items[0] {
+            Item::Fn(f) => {
+                assert_eq!(f.param_lists().count(), 2);
+                assert!(f.ret_ty().is_none());
+                let body = f.body().unwrap();
+                assert!(matches!(body.stmts().next(), Some(Stmt::Let(_))));
+            }
+            _ => unreachable!(),
+        }
+        match &items[4] {
+            Item::Fn(f) => match f.ret_ty().unwrap() {
+                Ty::Path(ty) => {
+                    let segment = ty.path().unwrap().segments().next().unwrap();
+                    assert!(segment.generic_args.is_some());
+                }
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn items() {
+        let file = parse_ok(
+            "
+use std::io::{self, Read as R, *};
+pub mod io;
+mod inner { pub(crate) fn f () {} }
+pub enum Poll<'a, T: 'a, const N: usize> { Ready(&'a T), Pending, Moved { pub from: [T; N] } }
+struct Unit;
+struct Pair(u8, u8);
+pub trait Future<T: Send + Sync> {
+    fn poll (&mut self): Poll<T>;
+    fn drop (self) { }
+}
+impl<T> Future<T> for Poll<T> {
+    fn poll (&mut self): Poll<T> { yield; }
+}
+impl Fd { fn close (mut self) (flush: bool) {} }
+",
+        );
+        let items: Vec<_> = file.items().collect();
+        assert_eq!(items.len(), 9);
+        assert_eq!(items[1].visibility(), Visibility::Public);
+        match &items[0] {
+            Item::Use(u) => {
+                let tree = u.use_tree().unwrap();
+                assert_eq!(tree.segments().count(), 2);
+                let trees: Vec<UseTree> = tree.use_trees().collect();
+                assert_eq!(trees[0].segments().next().unwrap().text(), "self");
+                assert_eq!(trees[1].rename().unwrap().text(), "R");
+                assert!(trees[2].is_glob());
+            }
+            _ => unreachable!(),
+        }
+        match &items[2] {
+            Item::Mod(m) => {
+                let inner = m.items().next().unwrap();
+                assert_eq!(inner.visibility(), Visibility::Crate);
+            }
+            _ => unreachable!(),
+        }
+        match &items[3] {
+            Item::Enum(e) => {
+                let params: Vec<_> = e.generic_params().collect();
+                let kinds: Vec<_> = params.iter().map(|param| param.kind()).collect();
+                assert_eq!(
+                    kinds,
+                    [
+                        GenericParamKind::Lifetime,
+                        GenericParamKind::Type,
+                        GenericParamKind::Const
+                    ]
+                );
+                assert_eq!(params[0].name().unwrap().text(), "'a");
+                assert_eq!(params[1].lifetime_bounds().count(), 1);
+                assert_eq!(params[1].bounds().count(), 0);
+                assert_eq!(params[2].ty().unwrap().syntax().text(), "usize");
+                let variants: Vec<_> = e.variants().collect();
+                assert_eq!(variants[0].fields_kind(), FieldsKind::Tuple);
+                assert_eq!(variants[1].fields_kind(), FieldsKind::Unit);
+                assert_eq!(variants[2].fields_kind(), FieldsKind::Named);
+                let field = variants[2].named_fields().next().unwrap();
+                assert_eq!(field.visibility(), Visibility::Public);
+            }
+            _ => unreachable!(),
+        }
+        match &items[6] {
+            Item::Trait(t) => {
+                let param = t.generic_params().next().unwrap();
+                assert_eq!(param.bounds().count(), 2);
+                let bodies: Vec<_> = t
+                    .items()
+                    .map(|item| match item {
+                        Item::Fn(f) => f.body().is_some(),
+                        _ => unreachable!(),
+                    })
+                    .collect();
+                assert_eq!(bodies, [false, true]);
+            }
+            _ => unreachable!(),
+        }
+        match &items[7] {
+            Item::Impl(i) => {
+                assert_eq!(i.trait_ty().unwrap().syntax().text(), "Future<T>");
+                assert_eq!(i.self_ty().unwrap().syntax().text(), "Poll<T>");
+                assert_eq!(i.items().count(), 1);
+            }
+            _ => unreachable!(),
+        }
+        match &items[8] {
+            Item::Impl(i) => {
+                assert!(i.trait_ty().is_none());
+                assert_eq!(i.self_ty().unwrap().syntax().text(), "Fd");
+                match i.items().next().unwrap() {
+                    Item::Fn(f) => {
+                        let lists: Vec<_> = f.param_lists().collect();
+                        assert_eq!(lists.len(), 2);
+                        let param = lists[0].self_param().unwrap();
+                        assert!(param.is_mut() && !param.is_ref());
+                        assert!(lists[1].self_param().is_none());
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn parameter_patterns() {
+        let file = parse_ok(
+            "fn f ((a, b): Pair) (Point { x, .. }: Point) (mut n: u8) ([_, rest @ ..]: [u8; 4]) {}",
+        );
+        let f = match file.items().next().unwrap() {
+            Item::Fn(f) => f,
+            _ => unreachable!(),
+        };
+        let kinds: Vec<_> = f
+            .param_lists()
+            .map(|list| match list.params().next().unwrap().pat().unwrap() {
+                Pat::Tuple(_) => "tuple",
+                Pat::Struct(pat) if pat.has_rest() => "struct",
+                Pat::Binding(pat) if pat.is_mut() => "binding",
+                Pat::Slice(_) => "slice",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(kinds, ["tuple", "struct", "binding", "slice"]);
+
+        // Alternatives need parentheses in parameters.
+        let (_, errors) = parse("fn f (A | B: T) {}");
+        assert_eq!(
+            errors[0].to_string(),
+            "expected `:` after parameter name, found `|`"
+        );
+        parse_ok("fn f ((A(x) | B(x)): T) {}");
+    }
+
+    #[test]
+    fn lifetime_and_const_params() {
+        parse_ok("fn f<'a, 'b: 'a + 'static, T: 'a + Send> (x: &'a T) (y: &'b u8) {}");
+        parse_ok(
+            "struct Buf<const N: usize> { data: [u8; N] }
+impl<const N: usize> Buf<N> {}",
+        );
+        let (_, errors) = parse("fn f<const N> () {}");
+        assert_eq!(errors[0].to_string(), "expected `:`, found `>`");
+        let (_, errors) = parse("fn f<'a: T> () {}");
+        assert_eq!(errors[0].to_string(), "expected lifetime, found identifier");
+    }
+
+    #[test]
+    fn collects_every_error() {
+        let (file, errors) = parse(
+            "
+fn a (x) {}
+struct B { x: }
+fn c () {}
+let d = 1;
+enum E { F }
+fn g () { 1 + }
+",
+        );
+        let messages: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "expected `:` after parameter name, found `)`",
+                "expected type, found `}`",
+                "expected item, found `let`",
+                "expected expression, found `}`",
+            ]
+        );
+        let kinds: Vec<_> = file
+            .items()
+            .map(|item| match item {
+                Item::Fn(_) => "fn",
+                Item::Enum(_) => "enum",
+                Item::Error(_) => "error",
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(kinds, ["error", "error", "fn", "error", "enum", "fn"]);
+    }
+}
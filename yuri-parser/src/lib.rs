@@ -1,7 +1,15 @@
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        assert_eq!(2 + 2, 4);
-    }
-}
+pub mod ast;
+mod error;
+mod expr;
+mod item;
+mod lossless;
+mod parser;
+mod pat;
+mod path;
+mod recovery;
+mod stmt;
+pub mod syntax;
+mod ty;
+
+pub use error::{Expected, ParseError, ParseErrorKind};
+pub use lossless::{parse, Parse};
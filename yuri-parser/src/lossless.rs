@@ -0,0 +1,320 @@
+//! Parsing a file into its concrete syntax tree, and reparsing it after an
+//! edit.
+//!
+//! The parser builds the tree as it goes. Trivia before a node belongs to
+//! the node around it, so a node starts and ends with a token of its own.
+
+use yuri_lexer::{FileId, Intern, Lexer, Span, TextEdit, Token, TokenKind};
+
+use crate::ast::{AstNode, SourceFile};
+use crate::parser::Parser;
+use crate::syntax::{GreenNode, NodeKind, SyntaxNode};
+use crate::ParseError;
+
+/// The concrete syntax tree of a source file along with its syntax errors.
+#[derive(Clone, Debug)]
+pub struct Parse {
+    green: GreenNode,
+    file: FileId,
+    errors: Vec<ParseError>,
+}
+
+/// Parses the tokens of `lexer` as a whole source file, interning names and
+/// literals into `interner`. Trivia is kept whether or not `lexer` was set
+/// up to produce it, and syntax errors are collected rather than ending the
+/// parse.
+pub fn parse<'a>(lexer: Lexer<'a>, interner: &'a dyn Intern) -> Parse {
+    let tokens: Vec<_> = lexer
+        .with_trivia()
+        .with_interner(interner)
+        .filter_map(Result::ok)
+        .collect();
+    let file = tokens.last().map_or(FileId::default(), |t| t.span.file);
+    let mut parser = Parser::new(tokens, interner);
+    parser.source_file();
+    let (green, errors) = parser.finish();
+    Parse {
+        green,
+        file,
+        errors,
+    }
+}
+
+impl Parse {
+    pub fn green(&self) -> &GreenNode {
+        &self.green
+    }
+
+    pub fn syntax(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone(), self.file)
+    }
+
+    pub fn source_file(&self) -> SourceFile {
+        SourceFile::cast(self.syntax()).unwrap()
+    }
+
+    pub fn errors(&self) -> &[ParseError] {
+        &self.errors
+    }
+
+    /// The tree of the source after `edit`, with the symbols of the new
+    /// tokens from `interner`, which must be the one the tree was parsed
+    /// with. An edit within a block, such as a function body, only rebuilds
+    /// that block and shares the rest of the tree; otherwise the whole file
+    /// is parsed again.
+    pub fn reparse(&self, edit: &TextEdit, interner: &dyn Intern) -> Parse {
+        if let Some(parse) = self.reparse_block(edit, interner) {
+            return parse;
+        }
+        let src = edit.apply(&self.green.to_string());
+        parse(Lexer::with_file(&src, self.file), interner)
+    }
+
+    fn reparse_block(&self, edit: &TextEdit, interner: &dyn Intern) -> Option<Parse> {
+        // The innermost block whose braces are both untouched by the edit.
+        let block = self
+            .syntax()
+            .descendants()
+            .filter(|node| {
+                let range = node.range();
+                node.kind() == NodeKind::Block
+                    && range.start < edit.range.start
+                    && edit.range.end < range.end
+            })
+            .last()?;
+        let range = block.range();
+        let mut src = block.text();
+        src.replace_range(
+            edit.range.start - range.start..edit.range.end - range.start,
+            &edit.text,
+        );
+
+        let tokens: Vec<_> = Lexer::with_file(&src, self.file)
+            .with_trivia()
+            .with_interner(interner)
+            .filter_map(Result::ok)
+            .collect();
+        if !is_block(&tokens) {
+            return None;
+        }
+        let mut parser = Parser::new(tokens, interner);
+        parser.block().ok()?;
+        if !parser.at(TokenKind::Eof) {
+            return None;
+        }
+        let (green, errors) = parser.finish();
+
+        let (start, end) = (range.start as u32, range.end as u32);
+        let delta = edit.text.len() as i64 - edit.range.len() as i64;
+        // Parsing the block on its own reports every error from after its
+        // `{` up to and including its `}`, but not those at the `{`, which
+        // come from what encloses it.
+        let before = self.errors.iter().filter(|e| e.span.lo <= start).cloned();
+        let inside = errors.iter().map(|e| shift(e, start.into()));
+        let after = self
+            .errors
+            .iter()
+            .filter(|e| e.span.lo >= end)
+            .map(|e| shift(e, delta));
+        Some(Parse {
+            green: block.replace_with(green),
+            file: self.file,
+            errors: before.chain(inside).chain(after).collect(),
+        })
+    }
+}
+
+fn shift(e: &ParseError, delta: i64) -> ParseError {
+    let span = Span::new(
+        e.span.file,
+        (i64::from(e.span.lo) + delta) as u32,
+        (i64::from(e.span.hi) + delta) as u32,
+    );
+    ParseError::new(e.kind.clone(), span)
+}
+
+/// Whether `tokens` are a `{`, then tokens with balanced braces, then the
+/// `}` matching the first `{`, so that they can be parsed as a block on
+/// their own.
+fn is_block(tokens: &[Token]) -> bool {
+    if tokens.first().map(|t| t.kind) != Some(TokenKind::BraceOpen) {
+        return false;
+    }
+    let mut depth = 0usize;
+    for (i, token) in tokens.iter().enumerate() {
+        match token.kind {
+            TokenKind::BraceOpen => depth += 1,
+            TokenKind::BraceClose => {
+                depth -= 1;
+                if depth == 0 {
+                    return tokens[i + 1..].iter().all(|t| t.kind == TokenKind::Eof);
+                }
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use yuri_lexer::Interner;
+
+    use super::*;
+
+    fn parse(src: &str) -> Parse {
+        super::parse(Lexer::new(src), &RefCell::new(Interner::new()))
+    }
+
+    #[test]
+    fn keeps_every_token() {
+        for src in [
+            "",
+            "  // nothing but a comment\n",
+            "//! Module docs.\n\n/// An item.\npub fn f (x: u8) : u8 { x /* same */ }\n",
+            "fn f () { let x = $; g(x,) }",
+            "struct S { x: }\nfn f (x) {}\nlet",
+            "fn f () { \"unterminated }",
+            "fn f ((a, b): P) { match x { S { ref x, y: -1..=9 } | [_, r @ ..] => (), _ => () } }",
+            "fn f () { while let Some(x) = it.next() { if let 'a'..='z' = x {} } }",
+            "fn f (g: fn (&'a [u8]) (&&T): Buf<{ N }>) { let v: Vec<Vec<u8>>= w; }",
+        ]
+        .iter()
+        {
+            assert_eq!(parse(src).syntax().text(), *src);
+        }
+    }
+
+    #[test]
+    fn tree() {
+        let parse = parse("fn f () { g(1) } // done");
+        assert_eq!(
+            format!("{:#?}", parse.syntax()),
+            r#"SourceFile@0..24
+  Fn@0..16
+    Keyword(Fn)@0..2 "fn"
+    Whitespace@2..3 " "
+    Name@3..4
+      Ident@3..4 "f"
+    Whitespace@4..5 " "
+    ParamList@5..7
+      ParenOpen@5..6 "("
+      ParenClose@6..7 ")"
+    Whitespace@7..8 " "
+    Block@8..16
+      BraceOpen@8..9 "{"
+      Whitespace@9..10 " "
+      ExprStmt@10..14
+        CallExpr@10..14
+          PathExpr@10..11
+            Path@10..11
+              NameRef@10..11
+                Ident@10..11 "g"
+          ParenOpen@11..12 "("
+          LitExpr@12..13
+            Int { base: Decimal, suffix: None }@12..13 "1"
+          ParenClose@13..14 ")"
+      Whitespace@14..15 " "
+      BraceClose@15..16 "}"
+  Whitespace@16..17 " "
+  LineComment@17..24 "// done"
+"#
+        );
+    }
+
+    #[test]
+    fn error_nodes() {
+        let parse = parse("let x;\nfn f () { 1 + ; }");
+        let errors: Vec<_> = parse
+            .syntax()
+            .descendants()
+            .filter(|node| node.kind() == NodeKind::Error)
+            .map(|node| node.text())
+            .collect();
+        assert_eq!(errors, ["let x;", "1 + ;"]);
+        assert_eq!(parse.errors().len(), 2);
+    }
+
+    #[test]
+    fn splits_glued_tokens() {
+        let nodes: Vec<_> = parse("fn f (x: A<B<C>>, y: &&T) {}")
+            .syntax()
+            .descendants()
+            .filter(|node| matches!(node.kind(), NodeKind::GenericArgs | NodeKind::RefTy))
+            .map(|node| node.text())
+            .collect();
+        assert_eq!(nodes, ["<B<C>>", "<C>", "&&T", "&T"]);
+
+        let fields: Vec<_> = parse("fn f () { t.0.1 }")
+            .syntax()
+            .descendants()
+            .filter(|node| node.kind() == NodeKind::FieldExpr)
+            .map(|node| node.text())
+            .collect();
+        assert_eq!(fields, ["t.0.1", "t.0"]);
+    }
+
+    /// Checks that reparsing after `edit` gives the same result as parsing
+    /// the edited source from scratch.
+    fn check_reparse(src: &str, edit: &TextEdit) -> (Parse, Parse) {
+        let interner = RefCell::new(Interner::new());
+        let old = super::parse(Lexer::new(src), &interner);
+        let new = old.reparse(edit, &interner);
+        let fresh = super::parse(Lexer::new(&edit.apply(src)), &interner);
+        assert_eq!(new.green(), fresh.green(), "{:?}", edit);
+        assert_eq!(new.errors(), fresh.errors(), "{:?}", edit);
+        (old, new)
+    }
+
+    fn nth_item(parse: &Parse, n: usize) -> GreenNode {
+        parse.syntax().children().nth(n).unwrap().green().clone()
+    }
+
+    #[test]
+    fn reparse_shares_the_rest_of_the_tree() {
+        let src = "fn f () { 1 }\n\nfn g () {\n    let x = 2;\n    x\n}\n\nstruct S;\n";
+        let at = src.find("2;").unwrap();
+        let (old, new) = check_reparse(src, &TextEdit::new(at..at + 1, "h(3)"));
+        assert!(nth_item(&old, 0).ptr_eq(&nth_item(&new, 0)));
+        assert!(nth_item(&old, 2).ptr_eq(&nth_item(&new, 2)));
+        assert!(!nth_item(&old, 1).ptr_eq(&nth_item(&new, 1)));
+    }
+
+    #[test]
+    fn reparse_errors() {
+        let src = "fn f () { 1 + }\nfn g () { 2 }\nfn h () { 3 + }\n";
+        // A new error in `g`, between the ones in `f` and `h`.
+        let at = src.find("2").unwrap();
+        let (_, new) = check_reparse(src, &TextEdit::new(at..at + 1, "2 +"));
+        assert_eq!(new.errors().len(), 3);
+        // Fixing the error in `h`.
+        let at = src.rfind('}').unwrap();
+        let (_, new) = check_reparse(src, &TextEdit::new(at..at, "4 "));
+        assert_eq!(new.errors().len(), 1);
+        // An error at the `{` of the block itself is reported by the
+        // enclosing block.
+        let (_, new) = check_reparse("fn f () { 1 { 2 } }", &TextEdit::new(14..15, "3"));
+        assert_eq!(new.errors().len(), 1);
+    }
+
+    #[test]
+    fn reparse_falls_back_to_the_whole_file() {
+        let src = "fn f () { g(1) }\nfn h () {}\n";
+        for edit in [
+            // Unbalances the braces of the body.
+            TextEdit::new(12..12, "{"),
+            // Touches the braces of the body.
+            TextEdit::new(8..10, "{ {"),
+            // Outside any block.
+            TextEdit::new(3..4, "f2"),
+            // Starts a string that runs past the end of the body.
+            TextEdit::new(12..12, "\""),
+        ]
+        .iter()
+        {
+            check_reparse(src, edit);
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use yuri_lexer::{Intern, Keyword, Span, Token, TokenKind};
+
+use crate::syntax::{Checkpoint, GreenNode, GreenNodeBuilder, NodeKind};
+use crate::{Expected, ParseError, ParseErrorKind};
+
+pub(crate) type PResult<T> = Result<T, ParseError>;
+
+/// A recursive-descent parser over the tokens of one source file, which
+/// builds the concrete syntax tree as it goes.
+///
+/// Trivia and doc comments are added to the tree but otherwise skipped.
+/// Lexical errors are left for the caller to report from its own lexer; the
+/// parser takes their `Error` tokens as `Error` expressions or items and
+/// reports no syntax error at them.
+pub(crate) struct Parser<'a> {
+    /// Every token of the input, trivia included.
+    tokens: Vec<Token<'a>>,
+    /// The index of the current token, which is never trivia.
+    pos: usize,
+    /// The index of the first token not added to the tree yet. Trivia is
+    /// added along with what follows it.
+    added: usize,
+    /// The span of the last consumed token, where the node being parsed
+    /// ends.
+    prev: Span,
+    builder: GreenNodeBuilder,
+    /// Where the symbols of the tokens came from, for those of the tokens
+    /// the parser splits.
+    interner: &'a dyn Intern,
+    /// Whether a path followed by `{` starts a struct expression. It does
+    /// not in the condition of an `if` or `while` or the scrutinee of a
+    /// `match`, where the `{` opens the body instead.
+    struct_allowed: bool,
+    /// Errors that the parser recovered from.
+    errors: Vec<ParseError>,
+    /// Everything checked for at the current token, to list in an error if
+    /// none of it is there.
+    expected: Vec<Expected>,
+}
+
+/// Whether the parser skips tokens of `kind`.
+fn is_trivia(kind: TokenKind) -> bool {
+    kind.is_trivia() || matches!(kind, TokenKind::DocComment(_))
+}
+
+/// Splits a token glued out of a `>` or `&` and what follows, such as `>>`,
+/// `>=` or `&&`, into its first character and the rest.
+pub(crate) fn unglue<'a>(token: &Token<'a>) -> Option<(Token<'a>, Token<'a>)> {
+    let (first, rest) = match token.kind {
+        TokenKind::GreaterGreater => (TokenKind::Greater, TokenKind::Greater),
+        TokenKind::GreaterEqual => (TokenKind::Greater, TokenKind::Equal),
+        TokenKind::GreaterGreaterEqual => (TokenKind::Greater, TokenKind::GreaterEqual),
+        TokenKind::AmpAmp => (TokenKind::Amp, TokenKind::Amp),
+        _ => return None,
+    };
+    let Span { file, lo, hi } = token.span;
+    let piece = |kind, lo, hi| Token {
+        kind,
+        span: Span::new(file, lo, hi),
+        text: &token.text[(lo - token.span.lo) as usize..(hi - token.span.lo) as usize],
+        symbol: None,
+    };
+    Some((piece(first, lo, lo + 1), piece(rest, lo + 1, hi)))
+}
+
+impl<'a> Parser<'a> {
+    /// A parser for `tokens`, trivia included, which must end with `Eof`
+    /// and have their symbols from `interner`.
+    pub(crate) fn new(tokens: Vec<Token<'a>>, interner: &'a dyn Intern) -> Self {
+        let file = tokens.last().expect("no `Eof` token").span.file;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            added: 0,
+            prev: Span::new(file, 0, 0),
+            builder: GreenNodeBuilder::new(),
+            interner,
+            struct_allowed: true,
+            errors: Vec::new(),
+            expected: Vec::new(),
+        };
+        parser.pos = parser.skip_trivia(0);
+        parser
+    }
+
+    /// The tree built, and the syntax errors.
+    pub(crate) fn finish(self) -> (GreenNode, Vec<ParseError>) {
+        (self.builder.finish(), self.errors)
+    }
+
+    /// The index of the first token from `pos` on that is not trivia.
+    fn skip_trivia(&self, mut pos: usize) -> usize {
+        while is_trivia(self.tokens[pos].kind) {
+            pos += 1;
+        }
+        pos
+    }
+
+    /// Adds the trivia before the current token to the current node.
+    pub(crate) fn trivia(&mut self) {
+        for token in &self.tokens[self.added..self.pos] {
+            self.builder.token(token.kind, token.text, token.symbol);
+        }
+        self.added = self.pos;
+    }
+
+    /// Adds the trivia before the current token to the tree, so that a node
+    /// starting here does not contain it. Trivia at the start of the input
+    /// is left for the root node instead.
+    fn leading_trivia(&mut self) {
+        if self.builder.in_node() || self.added > 0 {
+            self.trivia();
+        }
+    }
+
+    pub(crate) fn start_node(&mut self, kind: NodeKind) {
+        self.leading_trivia();
+        self.builder.start_node(kind);
+    }
+
+    pub(crate) fn finish_node(&mut self) {
+        self.builder.finish_node();
+    }
+
+    /// Parses the children of a node of `kind` with `f`. If `f` fails, the
+    /// node is left unfinished for error recovery to clean up.
+    pub(crate) fn node<T>(
+        &mut self,
+        kind: NodeKind,
+        f: impl FnOnce(&mut Self) -> PResult<T>,
+    ) -> PResult<T> {
+        self.start_node(kind);
+        let result = f(self)?;
+        self.finish_node();
+        Ok(result)
+    }
+
+    pub(crate) fn checkpoint(&mut self) -> Checkpoint {
+        self.leading_trivia();
+        self.builder.checkpoint()
+    }
+
+    /// Starts a node of `kind` that begins with everything parsed since
+    /// `checkpoint`.
+    pub(crate) fn start_node_at(&mut self, checkpoint: Checkpoint, kind: NodeKind) {
+        self.builder.start_node_at(checkpoint, kind);
+    }
+
+    /// Makes everything parsed since `checkpoint` a node of `kind`.
+    pub(crate) fn wrap(&mut self, checkpoint: Checkpoint, kind: NodeKind) {
+        self.builder.start_node_at(checkpoint, kind);
+        self.builder.finish_node();
+    }
+
+    /// Makes everything parsed or skipped since `checkpoint` an `Error`
+    /// node, after a syntax error there.
+    pub(crate) fn error_node(&mut self, checkpoint: Checkpoint) {
+        self.builder.flatten_at(checkpoint, NodeKind::Error);
+    }
+
+    /// The current token. The lexer always ends the input with `Eof`, which
+    /// is never consumed.
+    pub(crate) fn token(&self) -> Token<'a> {
+        self.tokens[self.pos]
+    }
+
+    pub(crate) fn peek(&self) -> TokenKind {
+        self.nth(0)
+    }
+
+    /// The kind of the token `n` tokens ahead, not counting trivia.
+    pub(crate) fn nth(&self, n: usize) -> TokenKind {
+        self.tokens[self.pos..]
+            .iter()
+            .filter(|token| !is_trivia(token.kind))
+            .nth(n)
+            .map_or(TokenKind::Eof, |token| token.kind)
+    }
+
+    pub(crate) fn span(&self) -> Span {
+        self.token().span
+    }
+
+    /// Whether the current token is of `kind`, without adding it to the
+    /// expected set. For lookahead that the error messages need not mention.
+    pub(crate) fn at(&self, kind: TokenKind) -> bool {
+        self.peek() == kind
+    }
+
+    /// Whether the current token is of `kind`, adding it to the expected set.
+    pub(crate) fn check(&mut self, kind: TokenKind) -> bool {
+        let expected = Expected::Token(kind);
+        if !self.expected.contains(&expected) {
+            self.expected.push(expected);
+        }
+        self.at(kind)
+    }
+
+    pub(crate) fn check_keyword(&mut self, keyword: Keyword) -> bool {
+        self.check(TokenKind::Keyword(keyword))
+    }
+
+    /// Consumes the current token, adding it to the current node.
+    pub(crate) fn bump(&mut self) -> Token<'a> {
+        let token = self.token();
+        if token.kind != TokenKind::Eof {
+            self.trivia();
+            self.builder.token(token.kind, token.text, token.symbol);
+            self.added = self.pos + 1;
+            self.pos = self.skip_trivia(self.pos + 1);
+            self.prev = token.span;
+            self.expected.clear();
+        }
+        token
+    }
+
+    /// Replaces the current token by `pieces`, which cover the same text,
+    /// as for the two tuple indices of `t.0.1`.
+    pub(crate) fn split_token(&mut self, pieces: impl IntoIterator<Item = Token<'a>>) {
+        let interner = self.interner;
+        let pieces = pieces.into_iter().map(|mut piece| {
+            if piece.kind.has_symbol() {
+                piece.symbol = Some(interner.intern(piece.text));
+            }
+            piece
+        });
+        self.tokens.splice(self.pos..=self.pos, pieces);
+    }
+
+    pub(crate) fn eat(&mut self, kind: TokenKind) -> bool {
+        let at = self.check(kind);
+        if at {
+            self.bump();
+        }
+        at
+    }
+
+    /// Like `eat` for `kind`, a `>` or `&`, but also splits it off the front
+    /// of a token such as `>>` or `&&`, as where generic arguments nest in
+    /// `Poll<Option<T>>`.
+    pub(crate) fn eat_split(&mut self, kind: TokenKind) -> bool {
+        if self.eat(kind) {
+            return true;
+        }
+        match unglue(&self.token()) {
+            Some((first, rest)) if first.kind == kind => {
+                self.trivia();
+                self.builder.token(first.kind, first.text, first.symbol);
+                self.tokens[self.pos] = rest;
+                self.prev = first.span;
+                self.expected.clear();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Like `eat`, but for tokens that error messages need not suggest,
+    /// such as an optional `mut` or a path's `::`.
+    pub(crate) fn eat_quietly(&mut self, kind: TokenKind) -> bool {
+        let at = self.at(kind);
+        if at {
+            self.bump();
+        }
+        at
+    }
+
+    pub(crate) fn eat_keyword(&mut self, keyword: Keyword) -> bool {
+        self.eat(TokenKind::Keyword(keyword))
+    }
+
+    pub(crate) fn expect(&mut self, kind: TokenKind) -> PResult<Token<'a>> {
+        if self.check(kind) {
+            Ok(self.bump())
+        } else {
+            Err(self.unexpected(None))
+        }
+    }
+
+    /// Like `expect`, but the error says what `kind` should come after.
+    pub(crate) fn expect_after(
+        &mut self,
+        kind: TokenKind,
+        after: &'static str,
+    ) -> PResult<Token<'a>> {
+        if self.check(kind) {
+            Ok(self.bump())
+        } else {
+            Err(self.unexpected(Some(after)))
+        }
+    }
+
+    /// An error for finding the current token instead of `expected` or
+    /// anything else checked for so far.
+    pub(crate) fn expected(&mut self, expected: Expected) -> ParseError {
+        if !self.expected.contains(&expected) {
+            self.expected.push(expected);
+        }
+        self.unexpected(None)
+    }
+
+    /// An error for finding the current token instead of anything in the
+    /// expected set.
+    pub(crate) fn unexpected(&self, after: Option<&'static str>) -> ParseError {
+        let kind = ParseErrorKind::Expected {
+            expected: self.expected.clone(),
+            after,
+            found: self.peek(),
+        };
+        ParseError::new(kind, self.span())
+    }
+
+    /// Records `error`, unless it is at the `Error` token of a lexical
+    /// error, which the lexer has reported already.
+    pub(crate) fn report(&mut self, error: ParseError) {
+        match error.kind {
+            ParseErrorKind::Expected {
+                found: TokenKind::Error,
+                ..
+            } => {}
+            _ => self.errors.push(error),
+        }
+    }
+
+    /// An identifier as a `Name` or `NameRef` node of `kind`.
+    pub(crate) fn name(&mut self, kind: NodeKind) -> PResult<()> {
+        if !self.check(TokenKind::Ident) {
+            return Err(self.unexpected(None));
+        }
+        self.node(kind, |p| {
+            p.bump();
+            Ok(())
+        })
+    }
+
+    /// The span from `lo` to the end of the last consumed token.
+    pub(crate) fn span_from(&self, lo: Span) -> Span {
+        lo.to(self.prev)
+    }
+
+    /// The kinds of the tokens consumed from `lo` on, trivia included.
+    pub(crate) fn consumed_since(&self, lo: Span) -> impl Iterator<Item = TokenKind> + '_ {
+        let consumed = &self.tokens[..self.pos];
+        let start = consumed.partition_point(|token| token.span.lo < lo.lo);
+        consumed[start..].iter().map(|token| token.kind)
+    }
+
+    /// Runs `f` with struct expressions allowed or not, e.g. to allow them
+    /// again inside parentheses in the condition of an `if`.
+    pub(crate) fn with_structs<T>(
+        &mut self,
+        allowed: bool,
+        f: impl FnOnce(&mut Self) -> PResult<T>,
+    ) -> PResult<T> {
+        let saved = self.struct_allowed;
+        self.struct_allowed = allowed;
+        let result = f(self);
+        self.struct_allowed = saved;
+        result
+    }
+
+    pub(crate) fn struct_allowed(&self) -> bool {
+        self.struct_allowed
+    }
+
+    /// Parses items with `f` separated by commas up to and including
+    /// `close`, allowing a trailing comma.
+    pub(crate) fn comma_list<T>(
+        &mut self,
+        close: TokenKind,
+        f: impl FnMut(&mut Self) -> PResult<T>,
+    ) -> PResult<Vec<T>> {
+        self.comma_list_trailing(close, f).map(|(items, _)| items)
+    }
+
+    /// Like `comma_list`, but also tells whether the list ended in a comma,
+    /// which makes `(a,)` a tuple rather than a parenthesized `a`.
+    pub(crate) fn comma_list_trailing<T>(
+        &mut self,
+        close: TokenKind,
+        mut f: impl FnMut(&mut Self) -> PResult<T>,
+    ) -> PResult<(Vec<T>, bool)> {
+        let mut items = Vec::new();
+        let mut trailing = false;
+        while !self.eat(close) {
+            items.push(f(self)?);
+            trailing = self.eat(TokenKind::Comma);
+            if !trailing {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok((items, trailing))
+    }
+}
@@ -0,0 +1,290 @@
+use yuri_lexer::{Keyword, TokenKind};
+
+use crate::parser::{PResult, Parser};
+use crate::path::PathStyle;
+use crate::syntax::NodeKind;
+use crate::Expected;
+
+impl Parser<'_> {
+    /// A pattern, possibly with alternatives `A | B`, as in `match` arms,
+    /// `let` and the elements of other patterns.
+    pub(crate) fn pat(&mut self) -> PResult<NodeKind> {
+        let cp = self.checkpoint();
+        let first = self.pat_no_alt()?;
+        if !self.at(TokenKind::Pipe) {
+            return Ok(first);
+        }
+        while self.eat(TokenKind::Pipe) {
+            self.pat_no_alt()?;
+        }
+        self.wrap(cp, NodeKind::OrPat);
+        Ok(NodeKind::OrPat)
+    }
+
+    /// A pattern without top-level alternatives, as in parameters, where a
+    /// `|` would be ambiguous, and after `@`.
+    pub(crate) fn pat_no_alt(&mut self) -> PResult<NodeKind> {
+        let cp = self.checkpoint();
+        let kind = match self.peek() {
+            TokenKind::Underscore => {
+                self.bump();
+                NodeKind::WildPat
+            }
+            TokenKind::DotDotEqual => {
+                self.bump();
+                self.range_bound()?;
+                NodeKind::RangePat
+            }
+            TokenKind::DotDot => {
+                self.bump();
+                NodeKind::RestPat
+            }
+            TokenKind::Keyword(Keyword::Ref) | TokenKind::Keyword(Keyword::Mut) => {
+                return self.binding();
+            }
+            // A lone identifier binds a name. Whether it actually names a
+            // unit variant such as `Init` is up to name resolution.
+            TokenKind::Ident
+                if !matches!(
+                    self.nth(1),
+                    TokenKind::ColonColon
+                        | TokenKind::ParenOpen
+                        | TokenKind::BraceOpen
+                        | TokenKind::DotDot
+                        | TokenKind::DotDotEqual
+                ) =>
+            {
+                return self.binding();
+            }
+            TokenKind::ParenOpen => {
+                self.bump();
+                let (pats, trailing) =
+                    self.comma_list_trailing(TokenKind::ParenClose, |p| p.pat())?;
+                if pats.len() == 1 && !trailing && pats[0] != NodeKind::RestPat {
+                    return Ok(pats[0]);
+                }
+                NodeKind::TuplePat
+            }
+            TokenKind::BracketOpen => {
+                self.bump();
+                self.comma_list(TokenKind::BracketClose, |p| p.pat())?;
+                NodeKind::SlicePat
+            }
+            _ if self.at_lit_expr() => {
+                self.lit_expr();
+                if self.at_range_op() {
+                    self.range_pat()?
+                } else {
+                    NodeKind::LitPat
+                }
+            }
+            _ if self.at_path_start() => {
+                self.path(PathStyle::Expr)?;
+                if self.at_range_op() {
+                    self.wrap(cp, NodeKind::PathExpr);
+                    self.range_pat()?
+                } else {
+                    self.path_pat_rest()?
+                }
+            }
+            _ => return Err(self.expected(Expected::Syntax("pattern"))),
+        };
+        self.wrap(cp, kind);
+        Ok(kind)
+    }
+
+    /// `x`, `mut x`, `ref x` or `ref mut x`, optionally followed by
+    /// `@ pattern`.
+    fn binding(&mut self) -> PResult<NodeKind> {
+        self.node(NodeKind::BindingPat, |p| {
+            p.eat_keyword(Keyword::Ref);
+            p.eat_keyword(Keyword::Mut);
+            p.name(NodeKind::Name)?;
+            if p.eat_quietly(TokenKind::At) {
+                p.pat_no_alt()?;
+            }
+            Ok(NodeKind::BindingPat)
+        })
+    }
+
+    /// What can follow the path at the start of a pattern: tuple-struct
+    /// fields, struct fields or nothing. Returns the kind of the pattern.
+    fn path_pat_rest(&mut self) -> PResult<NodeKind> {
+        if self.eat(TokenKind::ParenOpen) {
+            self.comma_list(TokenKind::ParenClose, |p| p.pat())?;
+            Ok(NodeKind::TupleStructPat)
+        } else if self.eat(TokenKind::BraceOpen) {
+            self.pat_fields()?;
+            Ok(NodeKind::StructPat)
+        } else {
+            Ok(NodeKind::PathPat)
+        }
+    }
+
+    /// The fields of a struct pattern after the `{`, which may end in `..`.
+    fn pat_fields(&mut self) -> PResult<()> {
+        loop {
+            if self.eat(TokenKind::BraceClose) {
+                return Ok(());
+            }
+            if self.eat(TokenKind::DotDot) {
+                self.expect(TokenKind::BraceClose)?;
+                return Ok(());
+            }
+            self.pat_field()?;
+            if !self.eat(TokenKind::Comma) {
+                self.expect(TokenKind::BraceClose)?;
+                return Ok(());
+            }
+        }
+    }
+
+    /// `name: pattern`, or a binding such as `ref mut name` for a field of
+    /// the same name.
+    fn pat_field(&mut self) -> PResult<()> {
+        self.node(NodeKind::PatField, |p| {
+            if p.at(TokenKind::Ident) && p.nth(1) == TokenKind::Colon {
+                p.name(NodeKind::NameRef)?;
+                p.bump();
+                p.pat()?;
+            } else {
+                p.binding()?;
+            }
+            Ok(())
+        })
+    }
+
+    fn at_range_op(&self) -> bool {
+        self.at(TokenKind::DotDotEqual) || self.at(TokenKind::DotDot)
+    }
+
+    /// A literal, a negated literal such as `-1`, or a path, as an
+    /// expression.
+    fn range_bound(&mut self) -> PResult<()> {
+        if self.at_lit_expr() {
+            self.lit_expr();
+            return Ok(());
+        }
+        if !self.at_path_start() {
+            return Err(self.expected(Expected::Syntax("literal or path")));
+        }
+        let cp = self.checkpoint();
+        self.path(PathStyle::Expr)?;
+        self.wrap(cp, NodeKind::PathExpr);
+        Ok(())
+    }
+
+    /// The rest of a range pattern after its start, from the `..=` or `..`
+    /// on.
+    fn range_pat(&mut self) -> PResult<NodeKind> {
+        let inclusive = self.bump().kind == TokenKind::DotDotEqual;
+        // `a..` has no end, as in `[0.., _]`, but `a..=` needs one.
+        if inclusive || self.at_lit_expr() || self.at_path_start() {
+            self.range_bound()?;
+        }
+        Ok(NodeKind::RangePat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expr::tests::{check, check_err};
+
+    /// Checks that `pat` parses to `expected` as the pattern of a `match` arm.
+    fn check_pat(pat: &str, expected: &str) {
+        let src = format!("match x {{ {} => () }}", pat);
+        check(&src, &format!("(match x ({} (tuple)))", expected));
+    }
+
+    #[test]
+    fn bindings() {
+        check_pat("_", "_");
+        check_pat("n", "n");
+        check_pat("mut buf", "mut buf");
+        check_pat("ref st", "ref st");
+        check_pat("ref mut st", "ref mut st");
+        check_pat("all @ [_, ..]", "all @ [_, ..]");
+        check_pat("n @ 1..=9", "n @ 1..=9");
+    }
+
+    #[test]
+    fn literals_and_ranges() {
+        check_pat("0", "0");
+        check_pat("-1", "(- 1)");
+        check_pat("\"yuri\"", "\"yuri\"");
+        check_pat("true", "true");
+        check_pat("'a'..='z'", "'a'..='z'");
+        check_pat("-10..0", "(- 10)..0");
+        check_pat("4KiB..", "4KiB..");
+        check_pat("..=MAX", "..=MAX");
+        check_pat("i32::MIN..=-1", "i32::MIN..=(- 1)");
+    }
+
+    #[test]
+    fn structured() {
+        check_pat("Poll::Pending", "Poll::Pending");
+        check_pat("Poll::Ready(n)", "Poll::Ready(n)");
+        check_pat("Some((a, b))", "Some((a, b))");
+        check_pat("(a,)", "(a)");
+        check_pat("(..)", "(..)");
+        check_pat("(first, .., last)", "(first, .., last)");
+        check_pat("Point { x, y: 0 }", "Point { x, y: 0 }");
+        check_pat("Point { ref mut x, .. }", "Point { ref mut x, .. }");
+        check_pat("Point {}", "Point {  }");
+        check_pat("[first, .., last]", "[first, .., last]");
+        check_pat("[rest @ .., _]", "[rest @ .., _]");
+    }
+
+    #[test]
+    fn alternatives() {
+        check_pat(
+            "Poll::Ready(0) | Poll::Pending",
+            "(Poll::Ready(0) | Poll::Pending)",
+        );
+        check_pat("| 1 | 2", "(1 | 2)");
+        check_pat("Some(1 | 2)", "Some((1 | 2))");
+        check_pat("n @ (1 | 2)", "n @ (1 | 2)");
+        check_pat("(Ok(v) | Err(v), _)", "((Ok(v) | Err(v)), _)");
+    }
+
+    #[test]
+    fn let_conditions() {
+        check(
+            "if let Some(x) = it.next() { x } else { 0 }",
+            "(if (let Some(x) (.next it)) {x} {0})",
+        );
+        check(
+            "while let [first, rest @ ..] = s { s = rest; }",
+            "(while (let [first, rest @ ..] s) {(= s rest);})",
+        );
+        check(
+            "if let Poll::Ready(n) | Poll::Done(n) = poll() {}",
+            "(if (let (Poll::Ready(n) | Poll::Done(n)) (call poll)) {} _)",
+        );
+    }
+
+    #[test]
+    fn let_statements() {
+        check("{ let (a, mut b) = t; }", "{(let (a, mut b) t)}");
+        check("{ let Point { x, .. } = p; }", "{(let Point { x, .. } p)}");
+        check("{ let [first, ..]: [u8; 4]; }", "{(let [first, ..] _)}");
+        check("{ let Ok(v) | Err(v) = r; }", "{(let (Ok(v) | Err(v)) r)}");
+    }
+
+    #[test]
+    fn errors() {
+        check_err(
+            "match x { Point { .., x } => () }",
+            "expected `}`, found `,`",
+        );
+        check_err(
+            "match x { ref 1 => () }",
+            "expected `mut` or identifier, found number literal",
+        );
+        check_err(
+            "match x { 1..= => () }",
+            "expected literal or path, found `=>`",
+        );
+        check_err("match x { -a => () }", "expected `}` or pattern, found `-`");
+    }
+}
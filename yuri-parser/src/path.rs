@@ -0,0 +1,98 @@
+use yuri_lexer::{Keyword, TokenKind};
+
+use crate::parser::{PResult, Parser};
+use crate::syntax::NodeKind;
+use crate::Expected;
+
+/// Where a path appears, which decides how generic arguments are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum PathStyle {
+    /// In expressions and patterns, `<` could be a comparison, so generic
+    /// arguments follow a `::` as in `size_of::<T>`.
+    Expr,
+    /// In types, `Poll<()>`.
+    Type,
+}
+
+fn path_keyword(kind: TokenKind) -> Option<Keyword> {
+    match kind {
+        TokenKind::Keyword(keyword) => match keyword {
+            Keyword::SelfValue | Keyword::SelfType | Keyword::Super | Keyword::Crate => {
+                Some(keyword)
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl Parser<'_> {
+    pub(crate) fn at_path_start(&self) -> bool {
+        self.at(TokenKind::Ident) || path_keyword(self.peek()).is_some()
+    }
+
+    pub(crate) fn path(&mut self, style: PathStyle) -> PResult<()> {
+        self.node(NodeKind::Path, |p| {
+            loop {
+                p.path_segment()?;
+                if style == PathStyle::Type && p.at(TokenKind::Less) {
+                    p.generic_args()?;
+                }
+                if !p.eat_quietly(TokenKind::ColonColon) {
+                    break;
+                }
+                if style == PathStyle::Expr && p.at(TokenKind::Less) {
+                    p.generic_args()?;
+                    if !p.eat_quietly(TokenKind::ColonColon) {
+                        break;
+                    }
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// The name of a path segment, which may be a keyword such as `self`,
+    /// as a `NameRef`.
+    pub(crate) fn path_segment(&mut self) -> PResult<()> {
+        if path_keyword(self.peek()).is_some() {
+            return self.node(NodeKind::NameRef, |p| {
+                p.bump();
+                Ok(())
+            });
+        }
+        if self.at(TokenKind::Ident) {
+            return self.name(NodeKind::NameRef);
+        }
+        Err(self.expected(Expected::Syntax("identifier")))
+    }
+
+    /// `<A, B>`. The closing `>` may be the first half of a `>>`.
+    pub(crate) fn generic_args(&mut self) -> PResult<()> {
+        self.node(NodeKind::GenericArgs, |p| {
+            p.expect(TokenKind::Less)?;
+            while !p.eat_split(TokenKind::Greater) {
+                p.generic_arg()?;
+                if !p.eat(TokenKind::Comma) {
+                    if !p.eat_split(TokenKind::Greater) {
+                        return Err(p.unexpected(None));
+                    }
+                    break;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// A type, or a const argument written as a literal or a block.
+    fn generic_arg(&mut self) -> PResult<()> {
+        if self.at_lit_expr() {
+            self.lit_expr();
+        } else if self.at(TokenKind::BraceOpen) {
+            self.block()?;
+        } else {
+            self.ty()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+//! Panic-mode error recovery: after a syntax error, the parser skips tokens
+//! up to a point where parsing can resume, and leaves an `Error` node in
+//! place of what it skipped.
+
+use yuri_lexer::{Span, TokenKind};
+
+use crate::parser::Parser;
+
+/// Where to stop skipping after an error.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Sync {
+    /// Up to the next item, or up to `close` that ends the list of items.
+    Item { close: TokenKind },
+    /// Past the next `;`, or up to the `}` ending the block or the next
+    /// item.
+    Stmt,
+}
+
+impl Parser<'_> {
+    /// Skips tokens up to the synchronization point `sync` after an error in
+    /// a construct that started at `lo`. The skipped tokens go into the
+    /// unfinished node of the construct, for the caller to turn into an
+    /// `Error` node.
+    /// Delimited groups are skipped whole, so that e.g. the `;`s inside a
+    /// nested block do not stop an item-level recovery. That includes the
+    /// groups the construct opened before the error, so that the `}` of
+    /// `a { # }` does not end the enclosing block.
+    pub(crate) fn recover(&mut self, lo: Span, sync: Sync) {
+        let mut depth = 0usize;
+        for kind in self.consumed_since(lo) {
+            depth = nest(depth, kind);
+        }
+        loop {
+            let kind = self.peek();
+            if kind == TokenKind::Eof {
+                break;
+            }
+            if depth == 0 {
+                let stop = match sync {
+                    Sync::Item { close } => kind == close || self.at_item_start(),
+                    Sync::Stmt => {
+                        if kind == TokenKind::Semicolon {
+                            self.bump();
+                            break;
+                        }
+                        kind == TokenKind::BraceClose || self.at_item_start()
+                    }
+                };
+                // Skip at least one token, so that recovery always makes
+                // progress.
+                if stop && self.span() != lo {
+                    break;
+                }
+            }
+            depth = nest(depth, kind);
+            self.bump();
+        }
+    }
+}
+
+/// The nesting depth of delimited groups after a token of `kind`.
+fn nest(depth: usize, kind: TokenKind) -> usize {
+    match kind {
+        TokenKind::ParenOpen | TokenKind::BracketOpen | TokenKind::BraceOpen => depth + 1,
+        TokenKind::ParenClose | TokenKind::BracketClose | TokenKind::BraceClose => {
+            depth.saturating_sub(1)
+        }
+        _ => depth,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use yuri_lexer::{Interner, Lexer};
+
+    use crate::ast::{AstNode, Expr, Item, Stmt};
+    use crate::Parse;
+
+    fn parse(src: &str) -> Parse {
+        crate::parse(Lexer::new(src), &RefCell::new(Interner::new()))
+    }
+
+    #[test]
+    fn resynchronizes_within_a_body() {
+        let parse = parse(
+            "
+fn f () {
+    let x = (1 + ];
+    let y = 2
+    g(x, y);
+    * ;
+    h()
+}
+",
+        );
+        let messages: Vec<_> = parse.errors().iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "expected expression, found `]`",
+                "expected `;`, found identifier",
+                "expected expression, found `;`",
+            ]
+        );
+        let body = match parse.source_file().items().next() {
+            Some(Item::Fn(f)) => f.body().unwrap(),
+            _ => unreachable!(),
+        };
+        let kinds: Vec<_> = body
+            .stmts()
+            .map(|stmt| match stmt {
+                Stmt::Let(_) => "let",
+                Stmt::Expr(stmt) if matches!(stmt.expr(), Some(Expr::Error(_))) => "error",
+                Stmt::Expr(stmt) if stmt.has_semicolon() => "semi",
+                Stmt::Expr(_) => "expr",
+                Stmt::Item(_) => "item",
+            })
+            .collect();
+        assert_eq!(kinds, ["error", "let", "semi", "error", "expr"]);
+    }
+
+    #[test]
+    fn lex_errors_are_not_reported_again() {
+        for src in [
+            "fn f () { let x = $; }",
+            "fn f () { g(1 $, 2) }",
+            "$ fn f () {}",
+        ]
+        .iter()
+        {
+            let lex_errors = Lexer::new(src).filter(Result::is_err).count();
+            let errors = parse(src).errors().to_vec();
+            assert_eq!(lex_errors + errors.len(), 1, "{}: {:?}", src, errors);
+        }
+    }
+
+    #[test]
+    fn stops_at_the_end_of_the_block() {
+        let src = "fn f () { a {#} b c }\nfn g () {}";
+        let parse = parse(src);
+        let messages: Vec<_> = parse.errors().iter().map(|e| e.to_string()).collect();
+        assert_eq!(messages, ["expected `}` or identifier, found `#`"]);
+        let spans: Vec<_> = parse
+            .source_file()
+            .items()
+            .map(|item| match item {
+                Item::Fn(f) => f.span(),
+                _ => unreachable!(),
+            })
+            .map(|span| &src[span.lo as usize..span.hi as usize])
+            .collect();
+        assert_eq!(spans, ["fn f () { a {#} b c }", "fn g () {}"]);
+    }
+
+    #[test]
+    fn one_error_per_bad_token() {
+        for src in ["fn f () {\n    ...\n}", "fn f () { let x = 1 . }"].iter() {
+            let errors = parse(src).errors().to_vec();
+            assert_eq!(errors.len(), 1, "{}: {:?}", src, errors);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use yuri_lexer::{Keyword, TokenKind};
+
+use crate::parser::{PResult, Parser};
+use crate::recovery::Sync;
+use crate::syntax::NodeKind;
+
+impl Parser<'_> {
+    /// `{ stmts }`
+    pub(crate) fn block(&mut self) -> PResult<()> {
+        self.node(NodeKind::Block, |p| {
+            p.expect(TokenKind::BraceOpen)?;
+            p.with_structs(true, |p| {
+                while !p.eat(TokenKind::BraceClose) {
+                    if p.eat_quietly(TokenKind::Semicolon) {
+                        continue;
+                    }
+                    if p.at(TokenKind::Eof) {
+                        return Err(p.unexpected(None));
+                    }
+                    let lo = p.span();
+                    let cp = p.checkpoint();
+                    if let Err(e) = p.stmt() {
+                        p.report(e);
+                        p.recover(lo, Sync::Stmt);
+                        p.error_node(cp);
+                        p.wrap(cp, NodeKind::ExprStmt);
+                    }
+                }
+                Ok(())
+            })
+        })
+    }
+
+    fn stmt(&mut self) -> PResult<()> {
+        if self.at(TokenKind::Keyword(Keyword::Let)) {
+            self.node(NodeKind::LetStmt, |p| {
+                p.bump();
+                p.pat()?;
+                if p.eat(TokenKind::Colon) {
+                    p.ty()?;
+                }
+                if p.eat(TokenKind::Equal) {
+                    p.expr()?;
+                }
+                if !p.eat(TokenKind::Semicolon) {
+                    let e = p.unexpected(None);
+                    if !p.can_begin_stmt() {
+                        return Err(e);
+                    }
+                    p.report(e);
+                }
+                Ok(())
+            })
+        } else if self.at_item_start() {
+            self.item()
+        } else {
+            self.node(NodeKind::ExprStmt, |p| {
+                let kind = p.expr_stmt()?;
+                if p.eat(TokenKind::Semicolon)
+                    || kind.is_block_like()
+                    || p.check(TokenKind::BraceClose)
+                {
+                    return Ok(());
+                }
+                let e = p.unexpected(Some("expression"));
+                if !p.can_begin_stmt() {
+                    return Err(e);
+                }
+                // The expression itself is fine and another statement
+                // follows, so carry on as if the `;` were there.
+                p.report(e);
+                Ok(())
+            })
+        }
+    }
+
+    /// Whether a statement can start at the current token, so that a
+    /// missing `;` before it can be assumed.
+    fn can_begin_stmt(&self) -> bool {
+        self.at(TokenKind::Keyword(Keyword::Let)) || self.at_item_start() || self.can_begin_expr()
+    }
+}
@@ -0,0 +1,684 @@
+//! The lossless concrete syntax tree of a source file.
+//!
+//! As in rowan, the tree has two layers. The green tree is immutable and
+//! knows nothing about positions: a node has a kind, its children and the
+//! length of its text, so that equal subtrees can be shared, e.g. between a
+//! tree and the tree of the file after an edit. The red tree of
+//! `SyntaxNode`s is a cursor over the green tree, built on demand, that knows
+//! the parent, offset and `NodeId` of each node.
+//!
+//! Every token of the source, trivia and lexical errors included, is a leaf,
+//! so the text of a tree is exactly the source it was built from.
+
+use std::fmt;
+use std::ops::Range;
+use std::rc::Rc;
+use std::sync::Arc;
+
+use yuri_lexer::{FileId, Span, Symbol, TokenKind};
+
+/// The kind of an interior node. Leaves are tokens and keep their
+/// `TokenKind`.
+///
+/// Each kind has a typed view of the same name in `ast`. Names being defined
+/// are `Name`s and names referring to something are `NameRef`s.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    SourceFile,
+    Fn,
+    Struct,
+    Enum,
+    Impl,
+    Trait,
+    Use,
+    Mod,
+    GenericParam,
+    ParamList,
+    /// `self`, `mut self`, `&self` or `&mut self` as a parameter.
+    SelfParam,
+    Param,
+    FieldDef,
+    Variant,
+    UseTree,
+    Name,
+    NameRef,
+    Path,
+    GenericArgs,
+    PathTy,
+    RefTy,
+    ArrayTy,
+    SliceTy,
+    TupleTy,
+    FnTy,
+    WildPat,
+    RestPat,
+    BindingPat,
+    LitPat,
+    RangePat,
+    PathPat,
+    TupleStructPat,
+    StructPat,
+    PatField,
+    TuplePat,
+    SlicePat,
+    OrPat,
+    Block,
+    LetStmt,
+    ExprStmt,
+    LitExpr,
+    PathExpr,
+    ParenExpr,
+    TupleExpr,
+    ArrayExpr,
+    RepeatExpr,
+    StructExpr,
+    ExprField,
+    UnaryExpr,
+    BinaryExpr,
+    RefExpr,
+    /// `a = b` and compound assignments such as `a += b`.
+    AssignExpr,
+    CastExpr,
+    CallExpr,
+    MethodCallExpr,
+    FieldExpr,
+    IndexExpr,
+    /// `expr?`
+    TryExpr,
+    RangeExpr,
+    IfExpr,
+    LoopExpr,
+    WhileExpr,
+    MatchExpr,
+    Arm,
+    /// `if cond` after the pattern of an arm.
+    MatchGuard,
+    /// `let pat = expr` as the condition of an `if` or `while`.
+    LetExpr,
+    BreakExpr,
+    ContinueExpr,
+    ReturnExpr,
+    YieldExpr,
+    /// Source that the parser skipped after a syntax error, in place of an
+    /// item, statement or expression.
+    Error,
+}
+
+impl NodeKind {
+    pub fn is_item(self) -> bool {
+        matches!(
+            self,
+            NodeKind::Fn
+                | NodeKind::Struct
+                | NodeKind::Enum
+                | NodeKind::Impl
+                | NodeKind::Trait
+                | NodeKind::Use
+                | NodeKind::Mod
+        )
+    }
+
+    pub fn is_ty(self) -> bool {
+        matches!(
+            self,
+            NodeKind::PathTy
+                | NodeKind::RefTy
+                | NodeKind::ArrayTy
+                | NodeKind::SliceTy
+                | NodeKind::TupleTy
+                | NodeKind::FnTy
+        )
+    }
+
+    pub fn is_pat(self) -> bool {
+        matches!(
+            self,
+            NodeKind::WildPat
+                | NodeKind::RestPat
+                | NodeKind::BindingPat
+                | NodeKind::LitPat
+                | NodeKind::RangePat
+                | NodeKind::PathPat
+                | NodeKind::TupleStructPat
+                | NodeKind::StructPat
+                | NodeKind::TuplePat
+                | NodeKind::SlicePat
+                | NodeKind::OrPat
+        )
+    }
+
+    /// Whether an expression of this kind ends in a block, so that it needs
+    /// no `;` to be a statement.
+    pub fn is_block_like(self) -> bool {
+        matches!(
+            self,
+            NodeKind::Block
+                | NodeKind::IfExpr
+                | NodeKind::LoopExpr
+                | NodeKind::WhileExpr
+                | NodeKind::MatchExpr
+        )
+    }
+
+    /// Whether nodes of this kind are expressions. A `Block` is one, and so
+    /// is an `Error` where an expression was expected.
+    pub fn is_expr(self) -> bool {
+        matches!(
+            self,
+            NodeKind::Block
+                | NodeKind::LitExpr
+                | NodeKind::PathExpr
+                | NodeKind::ParenExpr
+                | NodeKind::TupleExpr
+                | NodeKind::ArrayExpr
+                | NodeKind::RepeatExpr
+                | NodeKind::StructExpr
+                | NodeKind::UnaryExpr
+                | NodeKind::BinaryExpr
+                | NodeKind::RefExpr
+                | NodeKind::AssignExpr
+                | NodeKind::CastExpr
+                | NodeKind::CallExpr
+                | NodeKind::MethodCallExpr
+                | NodeKind::FieldExpr
+                | NodeKind::IndexExpr
+                | NodeKind::TryExpr
+                | NodeKind::RangeExpr
+                | NodeKind::IfExpr
+                | NodeKind::LoopExpr
+                | NodeKind::WhileExpr
+                | NodeKind::MatchExpr
+                | NodeKind::LetExpr
+                | NodeKind::BreakExpr
+                | NodeKind::ContinueExpr
+                | NodeKind::ReturnExpr
+                | NodeKind::YieldExpr
+                | NodeKind::Error
+        )
+    }
+}
+
+/// The position of a node in a preorder walk of the tree of its file, so
+/// that later passes can attach information to nodes in side tables instead
+/// of in the tree. The ids of a tree are `0..node_count()` of its root, so a
+/// side table can be a `Vec`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeId(u32);
+
+impl NodeId {
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+/// An immutable node of the green tree. Cloning one is cheap and shares it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GreenNode(Arc<GreenNodeData>);
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct GreenNodeData {
+    kind: NodeKind,
+    len: usize,
+    /// The number of nodes in this subtree, this one included.
+    nodes: u32,
+    children: Vec<GreenElement>,
+}
+
+/// A token of the green tree.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GreenToken(Arc<GreenTokenData>);
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+struct GreenTokenData {
+    kind: TokenKind,
+    text: Box<str>,
+    symbol: Option<Symbol>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GreenElement {
+    Node(GreenNode),
+    Token(GreenToken),
+}
+
+impl GreenNode {
+    pub fn new(kind: NodeKind, children: Vec<GreenElement>) -> Self {
+        let len = children.iter().map(GreenElement::len).sum();
+        let nodes = 1 + children
+            .iter()
+            .map(|child| match child {
+                GreenElement::Node(node) => node.0.nodes,
+                GreenElement::Token(_) => 0,
+            })
+            .sum::<u32>();
+        GreenNode(Arc::new(GreenNodeData {
+            kind,
+            len,
+            nodes,
+            children,
+        }))
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.0.kind
+    }
+
+    /// The length of the text of this node in bytes.
+    pub fn len(&self) -> usize {
+        self.0.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.len == 0
+    }
+
+    /// The number of nodes in this subtree, this one included.
+    pub fn node_count(&self) -> usize {
+        self.0.nodes as usize
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.0.children
+    }
+
+    /// Whether `self` and `other` are the same shared node, rather than just
+    /// equal ones.
+    pub fn ptr_eq(&self, other: &GreenNode) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// A copy of this node with its `index`th child replaced by `child`,
+    /// sharing all the other children.
+    pub fn replace_child(&self, index: usize, child: GreenElement) -> GreenNode {
+        let mut children = self.0.children.clone();
+        children[index] = child;
+        GreenNode::new(self.0.kind, children)
+    }
+}
+
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for child in self.children() {
+            match child {
+                GreenElement::Node(node) => write!(f, "{}", node)?,
+                GreenElement::Token(token) => f.write_str(token.text())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl GreenToken {
+    pub fn new(kind: TokenKind, text: &str, symbol: Option<Symbol>) -> Self {
+        GreenToken(Arc::new(GreenTokenData {
+            kind,
+            text: text.into(),
+            symbol,
+        }))
+    }
+
+    pub fn kind(&self) -> TokenKind {
+        self.0.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.0.text
+    }
+
+    /// The symbol the lexer interned for this token, if its kind
+    /// `has_symbol`.
+    pub fn symbol(&self) -> Option<Symbol> {
+        self.0.symbol
+    }
+}
+
+impl GreenElement {
+    pub fn len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.len(),
+            GreenElement::Token(token) => token.text().len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Builds a green tree from the starts and ends of its nodes and the tokens
+/// in between, in source order.
+#[derive(Debug, Default)]
+pub struct GreenNodeBuilder {
+    /// The nodes started but not finished, with the index in `children` of
+    /// their first child.
+    parents: Vec<(NodeKind, usize)>,
+    /// The children of the nodes in `parents`, one after the other.
+    children: Vec<GreenElement>,
+}
+
+/// A point in the building of a tree where a node can be started later on,
+/// once it is known which node the children added since belong to, as for
+/// the left operand of a binary expression.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Checkpoint {
+    len: usize,
+    depth: usize,
+}
+
+impl GreenNodeBuilder {
+    pub fn new() -> Self {
+        GreenNodeBuilder::default()
+    }
+
+    /// Whether a node has been started and not finished yet.
+    pub fn in_node(&self) -> bool {
+        !self.parents.is_empty()
+    }
+
+    pub fn start_node(&mut self, kind: NodeKind) {
+        self.parents.push((kind, self.children.len()));
+    }
+
+    pub fn token(&mut self, kind: TokenKind, text: &str, symbol: Option<Symbol>) {
+        self.children
+            .push(GreenElement::Token(GreenToken::new(kind, text, symbol)));
+    }
+
+    pub fn finish_node(&mut self) {
+        let (kind, first) = self.parents.pop().expect("no node to finish");
+        let children = self.children.drain(first..).collect();
+        self.children
+            .push(GreenElement::Node(GreenNode::new(kind, children)));
+    }
+
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            len: self.children.len(),
+            depth: self.parents.len(),
+        }
+    }
+
+    /// Starts a node of `kind` whose children begin with everything added
+    /// since `checkpoint`.
+    pub fn start_node_at(&mut self, checkpoint: Checkpoint, kind: NodeKind) {
+        assert_eq!(
+            checkpoint.depth,
+            self.parents.len(),
+            "a node started since the checkpoint is not finished"
+        );
+        self.parents.push((kind, checkpoint.len));
+    }
+
+    /// Makes everything added since `checkpoint` a node of `kind` that holds
+    /// just its tokens, dropping the nodes around them and finishing those
+    /// left unfinished, as for source that a syntax error made the parser
+    /// give up on.
+    pub fn flatten_at(&mut self, checkpoint: Checkpoint, kind: NodeKind) {
+        fn push_tokens(elem: GreenElement, tokens: &mut Vec<GreenElement>) {
+            match elem {
+                GreenElement::Node(node) => {
+                    for child in node.children() {
+                        push_tokens(child.clone(), tokens);
+                    }
+                }
+                GreenElement::Token(_) => tokens.push(elem),
+            }
+        }
+        assert!(checkpoint.depth <= self.parents.len());
+        self.parents.truncate(checkpoint.depth);
+        let mut tokens = Vec::new();
+        for child in self.children.drain(checkpoint.len..) {
+            push_tokens(child, &mut tokens);
+        }
+        self.children
+            .push(GreenElement::Node(GreenNode::new(kind, tokens)));
+    }
+
+    /// The tree, which must be a single finished node.
+    pub fn finish(mut self) -> GreenNode {
+        assert!(self.parents.is_empty(), "a node is not finished");
+        match (self.children.pop(), self.children.is_empty()) {
+            (Some(GreenElement::Node(node)), true) => node,
+            _ => panic!("the tree is not a single node"),
+        }
+    }
+}
+
+/// A node of the red tree: a green node together with its position in the
+/// tree.
+#[derive(Clone)]
+pub struct SyntaxNode(Rc<NodeData>);
+
+struct NodeData {
+    green: GreenNode,
+    parent: Option<SyntaxNode>,
+    /// The index of this node among the children of its parent.
+    index: usize,
+    /// The offset of this node's text from the start of the file.
+    offset: usize,
+    file: FileId,
+    id: NodeId,
+}
+
+/// A token of the red tree.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SyntaxToken {
+    parent: SyntaxNode,
+    index: usize,
+    offset: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl SyntaxNode {
+    /// The root of the tree of `file`.
+    pub fn new_root(green: GreenNode, file: FileId) -> Self {
+        SyntaxNode(Rc::new(NodeData {
+            green,
+            parent: None,
+            index: 0,
+            offset: 0,
+            file,
+            id: NodeId(0),
+        }))
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        self.0.green.kind()
+    }
+
+    pub fn green(&self) -> &GreenNode {
+        &self.0.green
+    }
+
+    /// The byte range of this node's text in the file.
+    pub fn range(&self) -> Range<usize> {
+        self.0.offset..self.0.offset + self.0.green.len()
+    }
+
+    pub fn span(&self) -> Span {
+        let range = self.range();
+        Span::new(self.0.file, range.start as u32, range.end as u32)
+    }
+
+    pub fn id(&self) -> NodeId {
+        self.0.id
+    }
+
+    pub fn text(&self) -> String {
+        self.0.green.to_string()
+    }
+
+    pub fn parent(&self) -> Option<SyntaxNode> {
+        self.0.parent.clone()
+    }
+
+    /// This node, its parent, its parent's parent and so on up to the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = SyntaxNode> {
+        std::iter::successors(Some(self.clone()), SyntaxNode::parent)
+    }
+
+    pub fn children_with_tokens(&self) -> impl Iterator<Item = SyntaxElement> {
+        let parent = self.clone();
+        let mut offset = self.0.offset;
+        let mut id = self.0.id.0 + 1;
+        let len = self.0.green.children().len();
+        (0..len).map(move |index| {
+            let child_offset = offset;
+            let child = &parent.0.green.children()[index];
+            offset += child.len();
+            match child {
+                GreenElement::Node(green) => {
+                    let child_id = NodeId(id);
+                    id += green.0.nodes;
+                    SyntaxElement::Node(SyntaxNode(Rc::new(NodeData {
+                        green: green.clone(),
+                        parent: Some(parent.clone()),
+                        index,
+                        offset: child_offset,
+                        file: parent.0.file,
+                        id: child_id,
+                    })))
+                }
+                GreenElement::Token(_) => SyntaxElement::Token(SyntaxToken {
+                    parent: parent.clone(),
+                    index,
+                    offset: child_offset,
+                }),
+            }
+        })
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = SyntaxNode> {
+        self.children_with_tokens().filter_map(|child| match child {
+            SyntaxElement::Node(node) => Some(node),
+            SyntaxElement::Token(_) => None,
+        })
+    }
+
+    /// This node and all the nodes below it, in preorder.
+    pub fn descendants(&self) -> impl Iterator<Item = SyntaxNode> {
+        let mut stack = vec![self.clone()];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            let len = stack.len();
+            stack.extend(node.children());
+            stack[len..].reverse();
+            Some(node)
+        })
+    }
+
+    /// Every token below this node, in source order.
+    pub fn tokens(&self) -> impl Iterator<Item = SyntaxToken> {
+        self.descendants().flat_map(|node| {
+            node.children_with_tokens().filter_map(|child| match child {
+                SyntaxElement::Token(token) => Some(token),
+                SyntaxElement::Node(_) => None,
+            })
+        })
+    }
+
+    /// The green tree of the whole file with this node replaced by `green`.
+    /// Everything off the path from this node to the root is shared with the
+    /// current tree.
+    pub fn replace_with(&self, green: GreenNode) -> GreenNode {
+        match &self.0.parent {
+            None => green,
+            Some(parent) => {
+                let green = parent
+                    .green()
+                    .replace_child(self.0.index, GreenElement::Node(green));
+                parent.replace_with(green)
+            }
+        }
+    }
+}
+
+impl PartialEq for SyntaxNode {
+    fn eq(&self, other: &SyntaxNode) -> bool {
+        self.0.offset == other.0.offset && self.0.green.ptr_eq(&other.0.green)
+    }
+}
+
+impl Eq for SyntaxNode {}
+
+/// `{:?}` shows the kind and range of the node, `{:#?}` the whole subtree.
+impl fmt::Debug for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !f.alternate() {
+            return write!(f, "{:?}@{:?}", self.kind(), self.range());
+        }
+        fn go(node: &SyntaxNode, depth: usize, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "{:indent$}{:?}", "", node, indent = depth * 2)?;
+            for child in node.children_with_tokens() {
+                match child {
+                    SyntaxElement::Node(node) => go(&node, depth + 1, f)?,
+                    SyntaxElement::Token(token) => {
+                        writeln!(f, "{:indent$}{:?}", "", token, indent = depth * 2 + 2)?
+                    }
+                }
+            }
+            Ok(())
+        }
+        go(self, 0, f)
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.green)
+    }
+}
+
+impl SyntaxToken {
+    pub fn green(&self) -> &GreenToken {
+        match &self.parent.green().children()[self.index] {
+            GreenElement::Token(token) => token,
+            GreenElement::Node(_) => unreachable!("a `SyntaxToken` pointing at a node"),
+        }
+    }
+
+    pub fn kind(&self) -> TokenKind {
+        self.green().kind()
+    }
+
+    pub fn text(&self) -> &str {
+        self.green().text()
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.text().len()
+    }
+
+    pub fn span(&self) -> Span {
+        let range = self.range();
+        Span::new(self.parent.0.file, range.start as u32, range.end as u32)
+    }
+
+    pub fn symbol(&self) -> Option<Symbol> {
+        self.green().symbol()
+    }
+
+    pub fn parent(&self) -> SyntaxNode {
+        self.parent.clone()
+    }
+}
+
+impl fmt::Debug for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}@{:?} {:?}", self.kind(), self.range(), self.text())
+    }
+}
+
+impl SyntaxElement {
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            SyntaxElement::Node(node) => node.range(),
+            SyntaxElement::Token(token) => token.range(),
+        }
+    }
+}
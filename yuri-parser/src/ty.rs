@@ -0,0 +1,130 @@
+use yuri_lexer::{Keyword, TokenKind};
+
+use crate::parser::{PResult, Parser};
+use crate::path::PathStyle;
+use crate::syntax::NodeKind;
+use crate::Expected;
+
+impl Parser<'_> {
+    /// A type, returning the kind of its node.
+    pub(crate) fn ty(&mut self) -> PResult<NodeKind> {
+        let cp = self.checkpoint();
+        let kind = match self.peek() {
+            // `&&T` is a reference to a reference.
+            TokenKind::Amp | TokenKind::AmpAmp => {
+                self.eat_split(TokenKind::Amp);
+                if self.at(TokenKind::Lifetime) {
+                    self.bump();
+                }
+                self.eat_keyword(Keyword::Mut);
+                self.ty()?;
+                NodeKind::RefTy
+            }
+            TokenKind::BracketOpen => {
+                self.bump();
+                self.ty()?;
+                if self.eat(TokenKind::BracketClose) {
+                    NodeKind::SliceTy
+                } else {
+                    self.expect(TokenKind::Semicolon)?;
+                    self.with_structs(true, |p| p.expr())?;
+                    self.expect(TokenKind::BracketClose)?;
+                    NodeKind::ArrayTy
+                }
+            }
+            TokenKind::ParenOpen => {
+                self.bump();
+                let (tys, trailing) =
+                    self.comma_list_trailing(TokenKind::ParenClose, |p| p.ty())?;
+                // `(T)` is just `T`, but `(T,)` is a tuple.
+                if tys.len() == 1 && !trailing {
+                    return Ok(tys[0]);
+                }
+                NodeKind::TupleTy
+            }
+            TokenKind::Keyword(Keyword::Fn) => {
+                self.bump();
+                loop {
+                    self.expect(TokenKind::ParenOpen)?;
+                    self.comma_list(TokenKind::ParenClose, |p| p.ty())?;
+                    if !self.at(TokenKind::ParenOpen) {
+                        break;
+                    }
+                }
+                if self.eat_quietly(TokenKind::Colon) {
+                    self.ty()?;
+                }
+                NodeKind::FnTy
+            }
+            _ if self.at_path_start() => {
+                self.path(PathStyle::Type)?;
+                NodeKind::PathTy
+            }
+            _ => return Err(self.expected(Expected::Syntax("type"))),
+        };
+        self.wrap(cp, kind);
+        Ok(kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expr::tests::{check, check_err};
+
+    /// Checks that `ty` parses to `expected` as the target of a cast.
+    fn check_ty(ty: &str, expected: &str) {
+        check(&format!("x as {}", ty), &format!("(as x {})", expected));
+    }
+
+    #[test]
+    fn paths_and_generics() {
+        check_ty("Fd", "Fd");
+        check_ty("Poll<()>", "Poll<()>");
+        check_ty("Result<u8, Error>", "Result<u8, Error>");
+        check_ty("T::Output", "T::Output");
+        check_ty("io::Poll<T>::Output", "io::Poll<T>::Output");
+        check_ty("Buf<1024, -1, {N}>", "Buf<1024, (- 1), {N}>");
+    }
+
+    #[test]
+    fn nested_generics_split_shifts() {
+        check_ty("Poll<Option<u8>>", "Poll<Option<u8>>");
+        check_ty("A<B<C<D>>>", "A<B<C<D>>>");
+        check("{ let v: Vec<u8>= w; }", "{(let v w)}");
+        check("{ let v: Vec<Vec<u8>>= w; }", "{(let v w)}");
+    }
+
+    #[test]
+    fn compound() {
+        check_ty("(u8, Fd)", "(u8, Fd)");
+        check_ty("(u8,)", "(u8)");
+        check_ty("(u8)", "u8");
+        check_ty("[u8; 1024]", "[u8; 1024]");
+        check_ty("[u8]", "[u8]");
+        check_ty("&mut [u8]", "&mut [u8]");
+        check_ty("&'a mut T", "&'a mut T");
+        check_ty("&&'static str", "&&'static str");
+    }
+
+    #[test]
+    fn fn_types() {
+        check_ty("fn ()", "fn ()");
+        check_ty("fn (Fd) (Fd) : Result", "fn (Fd) (Fd) : Result");
+        check_ty(
+            "fn (&mut [u8], usize): Poll<usize>",
+            "fn (&mut [u8], usize) : Poll<usize>",
+        );
+    }
+
+    #[test]
+    fn errors() {
+        check_err("x as Vec<u8; 4>", "expected `,` or `>`, found `;`");
+        check_err("x as [u8, 4]", "expected `]` or `;`, found `,`");
+        check_err("x as fn", "expected `(`, found end of file");
+        check_err(
+            "Vec<u8>::new()",
+            "comparison operators cannot be chained; use `::<...>` for generic arguments",
+        );
+        check_err("a < b < c", "comparison operators cannot be chained");
+    }
+}